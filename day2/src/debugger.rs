@@ -0,0 +1,290 @@
+use std::io::{self, BufRead, Write};
+
+use super::{parse, Aim, Depth, Horizontal, Instruction, Model};
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Breakpoint {
+    /// Stop when the depth goes past the value.
+    DepthAbove(u32),
+    /// Stop when the aim leaves 0 or comes back to it, the aim is unsigned
+    /// so it is the only sign change it can make.
+    AimSign,
+}
+
+impl Breakpoint {
+    fn is_hit(&self, before: &(Depth, Horizontal, Aim), after: &(Depth, Horizontal, Aim)) -> bool {
+        match self {
+            Breakpoint::DepthAbove(limit) => before.0 .0 <= *limit && after.0 .0 > *limit,
+            Breakpoint::AimSign => (before.2 .0 == 0) != (after.2 .0 == 0),
+        }
+    }
+}
+
+/// Steps through a course, keeping every position seen so it can rewind.
+pub struct Debugger {
+    model: Model,
+    instructions: Vec<Instruction>,
+    /// `history[i]` is the position after the first `i` instructions.
+    history: Vec<(Depth, Horizontal, Aim)>,
+    breakpoints: Vec<Breakpoint>,
+}
+
+impl Debugger {
+    pub fn new(model: Model, instructions: Vec<Instruction>) -> Debugger {
+        Debugger {
+            model,
+            instructions,
+            history: vec![(Depth(0), Horizontal(0), Aim::new(0))],
+            breakpoints: Vec::new(),
+        }
+    }
+
+    /// Line of the next instruction to run, starting at 1.
+    pub fn line(&self) -> usize {
+        self.history.len()
+    }
+
+    pub fn position(&self) -> (Depth, Horizontal, Aim) {
+        *self.history.last().unwrap()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.history.len() > self.instructions.len()
+    }
+
+    pub fn add_breakpoint(&mut self, breakpoint: Breakpoint) {
+        self.breakpoints.push(breakpoint);
+    }
+
+    pub fn step(&mut self) -> Result<(), String> {
+        if self.is_finished() {
+            return Err(String::from("end of course"));
+        }
+        let instruction = &self.instructions[self.line() - 1];
+        match self.model.step(self.position(), instruction) {
+            Ok(next) => {
+                self.history.push(next);
+                Ok(())
+            }
+            Err(_) => Err(format!(
+                "line {}: `{}` goes below 0",
+                self.line(),
+                instruction
+            )),
+        }
+    }
+
+    pub fn back(&mut self) -> Result<(), String> {
+        if self.history.len() == 1 {
+            return Err(String::from("start of course"));
+        }
+        self.history.pop();
+        Ok(())
+    }
+
+    /// Step until the next instruction is on `line`, a breakpoint fires or
+    /// the course ends. Returns the breakpoint that stopped the run.
+    pub fn run_to(&mut self, line: Option<usize>) -> Result<Option<Breakpoint>, String> {
+        while !self.is_finished() && Some(self.line()) != line {
+            let before = self.position();
+            self.step()?;
+            let after = self.position();
+            if let Some(breakpoint) = self
+                .breakpoints
+                .iter()
+                .find(|val| val.is_hit(&before, &after))
+            {
+                return Ok(Some(*breakpoint));
+            }
+        }
+        Ok(None)
+    }
+
+    fn print(&self) -> String {
+        let (depth, hor, aim) = self.position();
+        let next = match self.instructions.get(self.line() - 1) {
+            Some(instruction) => format!("{}", instruction),
+            None => String::from("end"),
+        };
+        format!(
+            "line {}: {:?} {:?} {:?}, next {}",
+            self.line(),
+            depth,
+            hor,
+            aim,
+            next
+        )
+    }
+
+    /// Run one command of the interactive mode and return what to display.
+    pub fn execute(&mut self, command: &str) -> String {
+        let words: Vec<&str> = command.split_whitespace().collect();
+        let count = |index: usize| -> Result<usize, String> {
+            match words.get(index) {
+                Some(val) => val.parse().map_err(|_| format!("not a number: {}", val)),
+                None => Ok(1),
+            }
+        };
+
+        let result = match words.as_slice() {
+            ["step" | "s", ..] => count(1)
+                .and_then(|n| (0..n).try_for_each(|_| self.step()))
+                .map(|_| None),
+            ["back" | "b", ..] => count(1)
+                .and_then(|n| (0..n).try_for_each(|_| self.back()))
+                .map(|_| None),
+            ["run" | "r", line] => match line.parse() {
+                Ok(line) => self.run_to(Some(line)),
+                Err(_) => Err(format!("not a line: {}", line)),
+            },
+            ["continue" | "c"] => self.run_to(None),
+            ["break", "depth", limit] => match limit.parse() {
+                Ok(limit) => {
+                    self.add_breakpoint(Breakpoint::DepthAbove(limit));
+                    return format!("break when depth > {}", limit);
+                }
+                Err(_) => Err(format!("not a depth: {}", limit)),
+            },
+            ["break", "aim"] => {
+                self.add_breakpoint(Breakpoint::AimSign);
+                return String::from("break when aim changes sign");
+            }
+            ["clear"] => {
+                self.breakpoints.clear();
+                return String::from("breakpoints cleared");
+            }
+            ["print" | "p"] => Ok(None),
+            _ => {
+                return String::from(
+                    "commands: step [n], back [n], run <line>, continue, \
+                     break depth <value>, break aim, clear, print, quit",
+                )
+            }
+        };
+
+        match result {
+            Ok(None) => self.print(),
+            Ok(Some(breakpoint)) => format!("stopped by {:?}\n{}", breakpoint, self.print()),
+            Err(err) => format!("{}\n{}", err, self.print()),
+        }
+    }
+}
+
+/// `debug <part1|part2> [file]`, reads commands from stdin.
+pub fn run(args: &[String]) {
+    if args.is_empty() {
+        panic!("usage: debug <part1|part2> [file]");
+    }
+    let model = Model::parse(&args[0]);
+    let filename = args.get(1).map(|val| val.as_str()).unwrap_or("input");
+    let mut debugger = Debugger::new(model, parse(filename));
+
+    print!("(debug) ");
+    io::stdout().flush().unwrap();
+    for line in io::stdin().lock().lines() {
+        let line = line.unwrap();
+        if line.trim() == "quit" || line.trim() == "q" {
+            break;
+        }
+        println!("{}", debugger.execute(&line));
+        print!("(debug) ");
+        io::stdout().flush().unwrap();
+    }
+}
+
+#[cfg(test)]
+mod test_debugger {
+    use super::*;
+
+    fn debugger(model: Model) -> Debugger {
+        let instructions = vec![
+            "forward 5",
+            "down 5",
+            "forward 8",
+            "up 3",
+            "down 8",
+            "forward 2",
+        ]
+        .into_iter()
+        .map(Instruction::parse)
+        .collect();
+        Debugger::new(model, instructions)
+    }
+
+    #[test]
+    fn test_step_back() {
+        let mut debugger = debugger(Model::Part2);
+
+        debugger.step().unwrap();
+        debugger.step().unwrap();
+        debugger.step().unwrap();
+        assert_eq!(debugger.position(), (Depth(40), Horizontal(13), Aim(5)));
+
+        debugger.back().unwrap();
+        assert_eq!(debugger.line(), 3);
+        assert_eq!(debugger.position(), (Depth(0), Horizontal(5), Aim(5)));
+
+        debugger.back().unwrap();
+        debugger.back().unwrap();
+        assert!(debugger.back().is_err());
+    }
+
+    #[test]
+    fn test_run_to() {
+        let mut debugger = debugger(Model::Part2);
+
+        assert_eq!(debugger.run_to(Some(4)), Ok(None));
+        assert_eq!(debugger.line(), 4);
+
+        assert_eq!(debugger.run_to(None), Ok(None));
+        assert!(debugger.is_finished());
+        assert_eq!(debugger.position(), (Depth(60), Horizontal(15), Aim(10)));
+        assert!(debugger.step().is_err());
+    }
+
+    #[test]
+    fn test_breakpoints() {
+        let mut debugger = debugger(Model::Part2);
+        debugger.add_breakpoint(Breakpoint::DepthAbove(30));
+        debugger.add_breakpoint(Breakpoint::AimSign);
+
+        assert_eq!(debugger.run_to(None), Ok(Some(Breakpoint::AimSign)));
+        assert_eq!(debugger.line(), 3);
+
+        assert_eq!(debugger.run_to(None), Ok(Some(Breakpoint::DepthAbove(30))));
+        assert_eq!(debugger.line(), 4);
+
+        assert_eq!(debugger.run_to(None), Ok(None));
+    }
+
+    #[test]
+    fn test_below_zero() {
+        let mut debugger = Debugger::new(Model::Part1, vec![Instruction::parse("up 1")]);
+        assert!(debugger.step().is_err());
+        assert_eq!(debugger.line(), 1);
+    }
+
+    #[test]
+    fn test_execute() {
+        let mut debugger = debugger(Model::Part1);
+
+        assert_eq!(
+            debugger.execute("step 2"),
+            "line 3: Depth(5) Horizontal(5) Aim(0), next forward 8"
+        );
+        assert_eq!(
+            debugger.execute("b"),
+            "line 2: Depth(0) Horizontal(5) Aim(0), next down 5"
+        );
+        debugger.execute("break depth 9");
+        assert_eq!(
+            debugger.execute("continue"),
+            "stopped by DepthAbove(9)\nline 6: Depth(10) Horizontal(13) Aim(0), next forward 2"
+        );
+        assert_eq!(
+            debugger.execute("run 7"),
+            "line 7: Depth(10) Horizontal(15) Aim(0), next end"
+        );
+        assert!(debugger.execute("step").starts_with("end of course"));
+    }
+}
@@ -0,0 +1,296 @@
+use super::{parse, Aim, BelowZero, Depth, Horizontal, Instruction, Model};
+
+/// What to do when an instruction leaves the envelope.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum OnViolation {
+    /// Stop before the faulty instruction.
+    Abort,
+    /// Record it and keep going, values below 0 are still set to 0.
+    Warn,
+    /// Record it and bring the value back to the limit.
+    Clamp,
+}
+
+impl OnViolation {
+    pub fn parse(raw: &str) -> OnViolation {
+        match raw {
+            "abort" => OnViolation::Abort,
+            "warn" => OnViolation::Warn,
+            "clamp" => OnViolation::Clamp,
+            _ => panic!("unknown violation behavior {}", raw),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Limit {
+    MaxDepth,
+    /// Back to depth 0 after diving, or above the surface.
+    Surface,
+    MaxAim,
+    /// The aim went below 0.
+    NegativeAim,
+    MaxHorizontal,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Violation {
+    /// Index of the instruction in the course, starting at 0.
+    pub index: usize,
+    pub limit: Limit,
+    /// Value the instruction would have reached.
+    pub value: i64,
+}
+
+/// Limits a course has to stay in, `None` means no limit.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Envelope {
+    pub max_depth: Option<u32>,
+    pub no_surfacing: bool,
+    pub max_aim: Option<u32>,
+    pub max_horizontal: Option<u32>,
+}
+
+#[derive(Debug)]
+pub struct Checked {
+    pub position: (Depth, Horizontal, Aim),
+    pub violations: Vec<Violation>,
+    pub aborted: bool,
+}
+
+/// Same as `Model::follow`, checking `envelope` after every `Model::step`.
+pub fn follow_instructions_checked(
+    model: Model,
+    instructions: &[Instruction],
+    envelope: &Envelope,
+    on_violation: OnViolation,
+) -> Checked {
+    let mut current = (Depth(0), Horizontal(0), Aim::new(0));
+    let mut violations = Vec::new();
+    let mut dived = false;
+
+    for (index, instruction) in instructions.iter().enumerate() {
+        let (depth, hor, aim) = current;
+        let (mut next_depth, mut next_hor, mut next_aim) = match model.step(current, instruction) {
+            Ok((depth, hor, aim)) => (depth.0 as i64, hor.0 as i64, aim.0 as i64),
+            Err(BelowZero::Depth(value)) => (value, hor.0 as i64, aim.0 as i64),
+            Err(BelowZero::Aim(value)) => (depth.0 as i64, hor.0 as i64, value),
+        };
+
+        let mut found: Vec<(Limit, i64, i64)> = Vec::new();
+        if let Some(max) = envelope.max_depth {
+            if next_depth > max as i64 {
+                found.push((Limit::MaxDepth, next_depth, max as i64));
+            }
+        }
+        if next_depth < 0 || (envelope.no_surfacing && dived && next_depth == 0) {
+            let floor = if envelope.no_surfacing && dived { 1 } else { 0 };
+            found.push((Limit::Surface, next_depth, floor));
+        }
+        if let Some(max) = envelope.max_aim {
+            if next_aim > max as i64 {
+                found.push((Limit::MaxAim, next_aim, max as i64));
+            }
+        }
+        if next_aim < 0 {
+            found.push((Limit::NegativeAim, next_aim, 0));
+        }
+        if let Some(max) = envelope.max_horizontal {
+            if next_hor > max as i64 {
+                found.push((Limit::MaxHorizontal, next_hor, max as i64));
+            }
+        }
+
+        for (limit, value, bound) in &found {
+            violations.push(Violation {
+                index,
+                limit: *limit,
+                value: *value,
+            });
+            let target = if on_violation == OnViolation::Clamp {
+                *bound
+            } else if *value < 0 {
+                0
+            } else {
+                continue;
+            };
+            match limit {
+                Limit::MaxDepth | Limit::Surface => next_depth = target,
+                Limit::MaxAim | Limit::NegativeAim => next_aim = target,
+                Limit::MaxHorizontal => next_hor = target,
+            }
+        }
+        if on_violation == OnViolation::Abort && !found.is_empty() {
+            return Checked {
+                position: current,
+                violations,
+                aborted: true,
+            };
+        }
+
+        current = position(next_depth, next_hor, next_aim);
+        dived = dived || next_depth > 0;
+    }
+
+    Checked {
+        position: current,
+        violations,
+        aborted: false,
+    }
+}
+
+fn position(depth: i64, hor: i64, aim: i64) -> (Depth, Horizontal, Aim) {
+    (Depth(depth as u32), Horizontal(hor as u32), Aim(aim as u32))
+}
+
+/// `check <part1|part2> <abort|warn|clamp> [file] [max-depth=N] [max-aim=N]
+/// [max-horizontal=N] [no-surfacing]`
+pub fn run(args: &[String]) {
+    if args.len() < 2 {
+        panic!("usage: check <part1|part2> <abort|warn|clamp> [file] [limits...]");
+    }
+    let model = Model::parse(&args[0]);
+    let on_violation = OnViolation::parse(&args[1]);
+    let mut filename = "input";
+    let mut envelope = Envelope::default();
+    for arg in &args[2..] {
+        match arg.split_once('=') {
+            Some(("max-depth", val)) => envelope.max_depth = Some(val.parse().unwrap()),
+            Some(("max-aim", val)) => envelope.max_aim = Some(val.parse().unwrap()),
+            Some(("max-horizontal", val)) => envelope.max_horizontal = Some(val.parse().unwrap()),
+            None if arg == "no-surfacing" => envelope.no_surfacing = true,
+            None => filename = arg,
+            _ => panic!("unknown limit {}", arg),
+        }
+    }
+
+    let checked = follow_instructions_checked(model, &parse(filename), &envelope, on_violation);
+    for violation in &checked.violations {
+        println!(
+            "instruction {}: {:?} ({})",
+            violation.index, violation.limit, violation.value
+        );
+    }
+    let (depth, hor, aim) = checked.position;
+    if checked.aborted {
+        println!("aborted at {:?} {:?} {:?}", depth, hor, aim);
+    } else {
+        println!("ended at {:?} {:?} {:?}", depth, hor, aim);
+    }
+}
+
+#[cfg(test)]
+mod test_envelope {
+    use super::*;
+
+    fn course() -> Vec<Instruction> {
+        vec![
+            "forward 5",
+            "down 5",
+            "forward 8",
+            "up 3",
+            "down 8",
+            "forward 2",
+        ]
+        .into_iter()
+        .map(Instruction::parse)
+        .collect()
+    }
+
+    #[test]
+    fn test_inside() {
+        let envelope = Envelope {
+            max_depth: Some(60),
+            no_surfacing: true,
+            max_aim: Some(10),
+            max_horizontal: Some(15),
+        };
+        let checked =
+            follow_instructions_checked(Model::Part2, &course(), &envelope, OnViolation::Abort);
+
+        assert!(checked.violations.is_empty());
+        assert!(!checked.aborted);
+        assert_eq!(checked.position, Model::Part2.follow(&course()));
+    }
+
+    #[test]
+    fn test_abort() {
+        let envelope = Envelope {
+            max_depth: Some(30),
+            ..Envelope::default()
+        };
+        let checked =
+            follow_instructions_checked(Model::Part2, &course(), &envelope, OnViolation::Abort);
+
+        assert!(checked.aborted);
+        assert_eq!(
+            checked.violations,
+            vec![Violation {
+                index: 2,
+                limit: Limit::MaxDepth,
+                value: 40
+            }]
+        );
+        assert_eq!(checked.position, (Depth(0), Horizontal(5), Aim(5)));
+    }
+
+    #[test]
+    fn test_warn() {
+        let envelope = Envelope {
+            max_aim: Some(6),
+            ..Envelope::default()
+        };
+        let checked =
+            follow_instructions_checked(Model::Part2, &course(), &envelope, OnViolation::Warn);
+
+        assert!(!checked.aborted);
+        // the aim stays above the limit for the last instruction
+        assert_eq!(checked.violations.len(), 2);
+        assert_eq!(checked.violations[0].index, 4);
+        assert_eq!(checked.violations[1].index, 5);
+        assert_eq!(checked.violations[1].limit, Limit::MaxAim);
+        assert_eq!(checked.position, Model::Part2.follow(&course()));
+    }
+
+    #[test]
+    fn test_clamp() {
+        let envelope = Envelope {
+            max_aim: Some(6),
+            max_horizontal: Some(14),
+            ..Envelope::default()
+        };
+        let checked =
+            follow_instructions_checked(Model::Part2, &course(), &envelope, OnViolation::Clamp);
+
+        assert_eq!(
+            checked
+                .violations
+                .iter()
+                .map(|val| val.index)
+                .collect::<Vec<usize>>(),
+            vec![4, 5]
+        );
+        // aim clamped to 6 for the last forward, horizontal clamped to 14
+        assert_eq!(checked.position, (Depth(52), Horizontal(14), Aim(6)));
+    }
+
+    #[test]
+    fn test_surface() {
+        let instructions: Vec<Instruction> = vec!["down 2", "up 2", "up 1"]
+            .into_iter()
+            .map(Instruction::parse)
+            .collect();
+        let envelope = Envelope {
+            no_surfacing: true,
+            ..Envelope::default()
+        };
+        let checked =
+            follow_instructions_checked(Model::Part1, &instructions, &envelope, OnViolation::Warn);
+
+        assert_eq!(checked.violations.len(), 2);
+        assert_eq!(checked.violations[0].index, 1);
+        assert_eq!(checked.violations[1].limit, Limit::Surface);
+        assert_eq!(checked.violations[1].value, -1);
+        assert_eq!(checked.position, (Depth(0), Horizontal(0), Aim(0)));
+    }
+}
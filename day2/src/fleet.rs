@@ -0,0 +1,203 @@
+use super::{parse, Aim, Depth, Horizontal, Instruction, Model};
+
+pub struct Submarine {
+    pub name: String,
+    instructions: Vec<Instruction>,
+}
+
+impl Submarine {
+    pub fn new(name: &str, instructions: Vec<Instruction>) -> Submarine {
+        Submarine {
+            name: String::from(name),
+            instructions,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Conflict {
+    pub tick: usize,
+    pub first: String,
+    pub second: String,
+    /// Squared distance on the (horizontal, depth) plane, 0 is a collision.
+    pub distance_squared: u64,
+}
+
+impl Conflict {
+    pub fn is_collision(&self) -> bool {
+        self.distance_squared == 0
+    }
+}
+
+#[derive(Debug)]
+pub struct Timeline {
+    /// `positions[tick][index]` is where submarine `index` is after `tick`
+    /// instructions, the first entry is the start.
+    pub positions: Vec<Vec<(Depth, Horizontal, Aim)>>,
+    pub conflicts: Vec<Conflict>,
+}
+
+/// Submarines running their course together, one instruction each per tick.
+/// A submarine at the end of its course stays where it is.
+pub struct Fleet {
+    model: Model,
+    submarines: Vec<Submarine>,
+    separation: u32,
+}
+
+impl Fleet {
+    pub fn new(model: Model, separation: u32) -> Fleet {
+        Fleet {
+            model,
+            submarines: Vec::new(),
+            separation,
+        }
+    }
+
+    pub fn add(&mut self, submarine: Submarine) {
+        self.submarines.push(submarine);
+    }
+
+    /// Run every course to its end. They all start at the same place, so the
+    /// start is not checked for conflicts.
+    pub fn simulate(&self) -> Timeline {
+        let start = (Depth(0), Horizontal(0), Aim::new(0));
+        let mut current = vec![start; self.submarines.len()];
+        let mut positions = vec![current.clone()];
+        let mut conflicts = Vec::new();
+        let length = self
+            .submarines
+            .iter()
+            .map(|val| val.instructions.len())
+            .max()
+            .unwrap_or(0);
+
+        for tick in 1..=length {
+            for (submarine, position) in self.submarines.iter().zip(current.iter_mut()) {
+                if let Some(instruction) = submarine.instructions.get(tick - 1) {
+                    *position = self.model.step(*position, instruction).unwrap_or_else(|_| {
+                        panic!("{}: `{}` goes below 0", submarine.name, instruction)
+                    });
+                }
+            }
+            conflicts.append(&mut self.conflicts(tick, &current));
+            positions.push(current.clone());
+        }
+
+        Timeline {
+            positions,
+            conflicts,
+        }
+    }
+
+    fn conflicts(&self, tick: usize, current: &[(Depth, Horizontal, Aim)]) -> Vec<Conflict> {
+        let limit = self.separation as u64 * self.separation as u64;
+        let mut result = Vec::new();
+        for first in 0..current.len() {
+            for second in first + 1..current.len() {
+                let depth = current[first].0 .0.abs_diff(current[second].0 .0) as u64;
+                let hor = current[first].1 .0.abs_diff(current[second].1 .0) as u64;
+                let distance_squared = depth * depth + hor * hor;
+                if distance_squared == 0 || distance_squared < limit {
+                    result.push(Conflict {
+                        tick,
+                        first: self.submarines[first].name.clone(),
+                        second: self.submarines[second].name.clone(),
+                        distance_squared,
+                    });
+                }
+            }
+        }
+        result
+    }
+}
+
+/// `fleet <part1|part2> <separation> <[name=]file>...`
+pub fn run(args: &[String]) {
+    if args.len() < 3 {
+        panic!("usage: fleet <part1|part2> <separation> <[name=]file>...");
+    }
+    let mut fleet = Fleet::new(Model::parse(&args[0]), args[1].parse().unwrap());
+    for arg in &args[2..] {
+        let (name, filename) = arg.split_once('=').unwrap_or((arg, arg));
+        fleet.add(Submarine::new(name, parse(filename)));
+    }
+
+    let timeline = fleet.simulate();
+    for (tick, positions) in timeline.positions.iter().enumerate() {
+        let line: Vec<String> = fleet
+            .submarines
+            .iter()
+            .zip(positions)
+            .map(|(submarine, (depth, hor, _))| {
+                format!("{} ({}, {})", submarine.name, hor.0, depth.0)
+            })
+            .collect();
+        println!("tick {}: {}", tick, line.join(" "));
+        for conflict in timeline.conflicts.iter().filter(|val| val.tick == tick) {
+            if conflict.is_collision() {
+                println!("  collision {} {}", conflict.first, conflict.second);
+            } else {
+                println!(
+                    "  too close {} {} ({:.1})",
+                    conflict.first,
+                    conflict.second,
+                    (conflict.distance_squared as f64).sqrt()
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_fleet {
+    use super::*;
+
+    fn submarine(name: &str, raw: Vec<&str>) -> Submarine {
+        Submarine::new(name, raw.into_iter().map(Instruction::parse).collect())
+    }
+
+    #[test]
+    fn test_simulate() {
+        let mut fleet = Fleet::new(Model::Part1, 0);
+        fleet.add(submarine("a", vec!["forward 5", "down 5", "forward 8"]));
+        fleet.add(submarine("b", vec!["down 5", "forward 5"]));
+
+        let timeline = fleet.simulate();
+        assert_eq!(timeline.positions.len(), 4);
+        assert_eq!(timeline.positions[1][0], (Depth(0), Horizontal(5), Aim(0)));
+        assert_eq!(timeline.positions[1][1], (Depth(5), Horizontal(0), Aim(0)));
+        // b is done and stays put
+        assert_eq!(timeline.positions[3][1], (Depth(5), Horizontal(5), Aim(0)));
+
+        assert_eq!(
+            timeline.conflicts,
+            vec![Conflict {
+                tick: 2,
+                first: String::from("a"),
+                second: String::from("b"),
+                distance_squared: 0
+            }]
+        );
+        assert!(timeline.conflicts[0].is_collision());
+    }
+
+    #[test]
+    fn test_separation() {
+        let mut fleet = Fleet::new(Model::Part2, 5);
+        fleet.add(submarine("a", vec!["forward 3", "down 1", "forward 3"]));
+        fleet.add(submarine("b", vec!["forward 1", "forward 9"]));
+        fleet.add(submarine("c", vec!["down 9", "forward 9"]));
+
+        let timeline = fleet.simulate();
+        let conflicts: Vec<(usize, &str, &str)> = timeline
+            .conflicts
+            .iter()
+            .map(|val| (val.tick, val.first.as_str(), val.second.as_str()))
+            .collect();
+        // a and b end exactly 5 apart, which is allowed
+        assert_eq!(conflicts, vec![(1, "a", "b"), (1, "a", "c"), (1, "b", "c")]);
+        assert_eq!(timeline.positions[3][0], (Depth(3), Horizontal(6), Aim(1)));
+        assert_eq!(timeline.positions[3][1], (Depth(0), Horizontal(10), Aim(0)));
+    }
+}
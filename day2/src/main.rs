@@ -1,9 +1,11 @@
+use std::env;
+use std::fmt;
 use std::fs;
 
 use std::cmp::{Ord, Ordering, PartialOrd};
 use std::ops::Add;
 
-#[derive(Debug, Eq, PartialOrd, Copy, Clone)]
+#[derive(Debug, Eq, Copy, Clone)]
 struct Depth(u32);
 
 impl Depth {
@@ -12,11 +14,11 @@ impl Depth {
     }
 
     pub fn up(&mut self, val: u32) {
-        self.0 = self.0 - val;
+        self.0 -= val;
     }
 
     pub fn down(&mut self, val: u32) {
-        self.0 = self.0 + val;
+        self.0 += val;
     }
 }
 
@@ -33,6 +35,12 @@ impl Ord for Depth {
     }
 }
 
+impl PartialOrd for Depth {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
 impl Add for Depth {
     type Output = Self;
     fn add(self, other: Self) -> Self::Output {
@@ -40,7 +48,7 @@ impl Add for Depth {
     }
 }
 
-#[derive(Debug, Eq, PartialOrd, Copy, Clone)]
+#[derive(Debug, Eq, Copy, Clone)]
 struct Horizontal(u32);
 
 impl Horizontal {
@@ -49,7 +57,7 @@ impl Horizontal {
     }
 
     pub fn foward(&mut self, val: u32) {
-        self.0 = self.0 + val;
+        self.0 += val;
     }
 }
 
@@ -65,7 +73,19 @@ impl Ord for Horizontal {
     }
 }
 
-#[derive(PartialEq, Debug)]
+impl PartialOrd for Horizontal {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+mod debugger;
+mod envelope;
+mod fleet;
+mod optimizer;
+mod planner;
+
+#[derive(PartialEq, Debug, Clone, Copy)]
 enum Mouvement {
     Forward,
     Up,
@@ -73,14 +93,19 @@ enum Mouvement {
     Unkwown,
 }
 
+#[derive(Debug, PartialEq, Clone)]
 struct Instruction {
     verb: Mouvement,
     measure: u32,
 }
 
 impl Instruction {
+    pub fn new(verb: Mouvement, measure: u32) -> Instruction {
+        Instruction { verb, measure }
+    }
+
     pub fn parse(raw: &str) -> Instruction {
-        let mut data: Vec<&str> = raw.split(" ").collect();
+        let data: Vec<&str> = raw.split(' ').collect();
         let verb_str = data[0];
         let verb = match verb_str {
             "forward" => Mouvement::Forward,
@@ -90,24 +115,34 @@ impl Instruction {
         };
         let measure: u32 = data[1].parse().unwrap();
 
-        Instruction {
-            verb: verb,
-            measure: measure,
-        }
+        Instruction { verb, measure }
+    }
+}
+
+impl fmt::Display for Instruction {
+    /// Same format as the course files, so the output can be parsed back.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let verb = match self.verb {
+            Mouvement::Forward => "forward",
+            Mouvement::Up => "up",
+            Mouvement::Down => "down",
+            Mouvement::Unkwown => "unknown",
+        };
+        write!(f, "{} {}", verb, self.measure)
     }
 }
 
-#[derive(Debug, Eq, Clone)]
+#[derive(Debug, Eq, Clone, Copy)]
 struct Aim(u32);
 impl Aim {
     pub fn new(val: u32) -> Aim {
         Aim(val)
     }
     pub fn down(&mut self, val: u32) {
-        self.0 = self.0 + val;
+        self.0 += val;
     }
     pub fn up(&mut self, val: u32) {
-        self.0 = self.0 - val;
+        self.0 -= val;
     }
 }
 
@@ -119,59 +154,113 @@ impl PartialEq for Aim {
 
 fn parse(filename: &str) -> Vec<Instruction> {
     let content = fs::read_to_string(filename).expect("can't read input");
-    content
-        .lines()
-        .into_iter()
-        .map(|value| Instruction::parse(value))
-        .collect()
+    content.lines().map(Instruction::parse).collect()
 }
 
 fn follow_instructions_part1(instructions: &Vec<Instruction>) -> (Depth, Horizontal) {
-    let mut depth = Depth::new("0");
-    let mut hor = Horizontal::new("0");
+    let mut position = (Depth::new("0"), Horizontal::new("0"), Aim::new(0));
 
     for instruction in instructions {
-        match instruction.verb {
-            Mouvement::Forward => hor.foward(instruction.measure),
-            Mouvement::Up => depth.up(instruction.measure),
-            Mouvement::Down => depth.down(instruction.measure),
-            _ => panic!("oh shit"),
-        }
+        position = Model::Part1.step(position, instruction).expect("oh shit");
     }
-    (depth, hor)
+    (position.0, position.1)
 }
 
 fn follow_instructions_part2(instructions: &Vec<Instruction>) -> (Depth, Horizontal, Aim) {
-    let mut depth = Depth::new("0");
-    let mut hor = Horizontal::new("0");
-    let mut aim = Aim::new(0);
+    let mut position = (Depth::new("0"), Horizontal::new("0"), Aim::new(0));
 
     for instruction in instructions {
-        match instruction.verb {
-            Mouvement::Forward => {
-                hor.foward(instruction.measure);
-                depth.down(aim.0 * instruction.measure);
+        position = Model::Part2.step(position, instruction).expect("oh shit");
+    }
+    position
+}
+
+/// An instruction taking the depth or the aim below 0, with the value it
+/// would reach.
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum BelowZero {
+    Depth(i64),
+    Aim(i64),
+}
+
+/// Movement model used to interpret a course.
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Model {
+    /// `up`/`down` change the depth directly.
+    Part1,
+    /// `up`/`down` change the aim, `forward` dives by `aim * measure`.
+    Part2,
+}
+
+impl Model {
+    pub fn parse(raw: &str) -> Model {
+        match raw {
+            "part1" => Model::Part1,
+            "part2" => Model::Part2,
+            _ => panic!("unknown model {}", raw),
+        }
+    }
+
+    /// Run the course, the aim is always 0 for the part1 model.
+    pub fn follow(&self, instructions: &Vec<Instruction>) -> (Depth, Horizontal, Aim) {
+        match self {
+            Model::Part1 => {
+                let (depth, hor) = follow_instructions_part1(instructions);
+                (depth, hor, Aim::new(0))
+            }
+            Model::Part2 => follow_instructions_part2(instructions),
+        }
+    }
+
+    /// Apply a single instruction, every way of running a course goes
+    /// through here.
+    pub fn step(
+        &self,
+        position: (Depth, Horizontal, Aim),
+        instruction: &Instruction,
+    ) -> Result<(Depth, Horizontal, Aim), BelowZero> {
+        let (mut depth, mut hor, mut aim) = position;
+        let measure = instruction.measure;
+        match (self, instruction.verb) {
+            (_, Mouvement::Unkwown) => panic!("oh shit"),
+            (Model::Part1, Mouvement::Forward) => hor.foward(measure),
+            (Model::Part1, Mouvement::Up) if measure > depth.0 => {
+                return Err(BelowZero::Depth(depth.0 as i64 - measure as i64))
             }
-            Mouvement::Up => {
-                aim.up(instruction.measure);
+            (Model::Part1, Mouvement::Up) => depth.up(measure),
+            (Model::Part1, Mouvement::Down) => depth.down(measure),
+            (Model::Part2, Mouvement::Forward) => {
+                hor.foward(measure);
+                depth.down(aim.0 * measure);
             }
-            Mouvement::Down => {
-                aim.down(instruction.measure);
+            (Model::Part2, Mouvement::Up) if measure > aim.0 => {
+                return Err(BelowZero::Aim(aim.0 as i64 - measure as i64))
             }
-            _ => panic!("oh shit"),
+            (Model::Part2, Mouvement::Up) => aim.up(measure),
+            (Model::Part2, Mouvement::Down) => aim.down(measure),
         }
+        Ok((depth, hor, aim))
     }
-    (depth, hor, aim)
 }
 
 fn main() {
+    let args: Vec<String> = env::args().collect();
+    match args.get(1).map(|val| val.as_str()) {
+        Some("plan") => return planner::run(&args[2..]),
+        Some("optimize") => return optimizer::run(&args[2..]),
+        Some("debug") => return debugger::run(&args[2..]),
+        Some("check") => return envelope::run(&args[2..]),
+        Some("fleet") => return fleet::run(&args[2..]),
+        _ => (),
+    }
+
     let instructions = parse("input");
-    let (depth, ho) = follow_instructions_part1(&instructions);
+    let (depth, ho, _aim) = Model::Part1.follow(&instructions);
 
     println!("response part1 {}", depth.0 * ho.0);
 
-    let (depth, ho, aim) = follow_instructions_part2(&instructions);
-    println!("response part1 {}", depth.0 * ho.0);
+    let (depth, ho, _aim) = Model::Part2.follow(&instructions);
+    println!("response part2 {}", depth.0 * ho.0);
 }
 
 #[cfg(test)]
@@ -229,10 +318,7 @@ mod test_function {
             "down 8",
             "forward 2",
         ];
-        let instructions = raw
-            .into_iter()
-            .map(|value| Instruction::parse(value))
-            .collect();
+        let instructions = raw.into_iter().map(Instruction::parse).collect();
         let (depth, ho) = follow_instructions_part1(&instructions);
 
         assert_eq!(depth, Depth::new("10"));
@@ -249,14 +335,20 @@ mod test_function {
             "down 8",
             "forward 2",
         ];
-        let instructions = raw
-            .into_iter()
-            .map(|value| Instruction::parse(value))
-            .collect();
+        let instructions = raw.into_iter().map(Instruction::parse).collect();
         let (depth, ho, aim) = follow_instructions_part2(&instructions);
 
         assert_eq!(depth, Depth::new("60"));
         assert_eq!(ho, Horizontal::new("15"));
         assert_eq!(aim, Aim::new(10));
     }
+
+    #[test]
+    fn test_step_below_zero() {
+        let start = (Depth::new("2"), Horizontal::new("0"), Aim::new(1));
+        let up = Instruction::parse("up 3");
+
+        assert_eq!(Model::Part1.step(start, &up), Err(BelowZero::Depth(-1)));
+        assert_eq!(Model::Part2.step(start, &up), Err(BelowZero::Aim(-2)));
+    }
 }
@@ -0,0 +1,230 @@
+use super::{parse, Aim, Depth, Horizontal, Instruction, Model, Mouvement};
+
+/// Result of `optimize`: the shorter course and the final position of both
+/// versions, so the caller can check nothing changed.
+#[derive(Debug)]
+pub struct Optimization {
+    pub course: Vec<Instruction>,
+    pub before: usize,
+    pub after: usize,
+    pub original_end: (Depth, Horizontal, Aim),
+    pub optimized_end: (Depth, Horizontal, Aim),
+}
+
+impl Optimization {
+    pub fn is_equivalent(&self) -> bool {
+        self.original_end == self.optimized_end
+    }
+
+    pub fn removed(&self) -> usize {
+        self.before - self.after
+    }
+}
+
+/// Compress `instructions` without changing where `model` ends up.
+pub fn optimize(model: Model, instructions: &Vec<Instruction>) -> Optimization {
+    let course = match model {
+        Model::Part1 => compress_part1(instructions),
+        Model::Part2 => compress_part2(instructions),
+    };
+
+    Optimization {
+        before: instructions.len(),
+        after: course.len(),
+        original_end: model.follow(instructions),
+        optimized_end: model.follow(&course),
+        course,
+    }
+}
+
+/// Every instruction commutes in part1: the course is one `forward` with the
+/// total and one vertical move with the net depth.
+fn compress_part1(instructions: &[Instruction]) -> Vec<Instruction> {
+    let mut forward: u32 = 0;
+    let mut depth: i64 = 0;
+    for instruction in instructions {
+        match instruction.verb {
+            Mouvement::Forward => forward += instruction.measure,
+            Mouvement::Up => depth -= instruction.measure as i64,
+            Mouvement::Down => depth += instruction.measure as i64,
+            _ => panic!("oh shit"),
+        }
+    }
+
+    let mut result = Vec::new();
+    if forward > 0 {
+        result.push(Instruction::new(Mouvement::Forward, forward));
+    }
+    push_vertical(&mut result, depth);
+    result
+}
+
+/// In part2 only the aim at each `forward` matters: runs of `up`/`down` are
+/// replaced by their net change and consecutive `forward` are summed. A run
+/// cancelling out makes the `forward` around it adjacent, so they merge too.
+fn compress_part2(instructions: &[Instruction]) -> Vec<Instruction> {
+    let mut result: Vec<Instruction> = Vec::new();
+    for instruction in instructions {
+        if instruction.measure == 0 {
+            continue;
+        }
+        match instruction.verb {
+            Mouvement::Forward => match result.last_mut() {
+                Some(last) if last.verb == Mouvement::Forward => {
+                    last.measure += instruction.measure
+                }
+                _ => result.push(instruction.clone()),
+            },
+            Mouvement::Up | Mouvement::Down => {
+                let mut net = vertical(instruction);
+                if let Some(last) = result.last() {
+                    if last.verb != Mouvement::Forward {
+                        net += vertical(last);
+                        result.pop();
+                    }
+                }
+                push_vertical(&mut result, net);
+
+                // the aim changes cancelled out, join the forward around them
+                if net == 0 && result.last().map(|val| val.verb) == Some(Mouvement::Forward) {
+                    let last = result.pop().unwrap();
+                    match result.last_mut() {
+                        Some(previous) if previous.verb == Mouvement::Forward => {
+                            previous.measure += last.measure
+                        }
+                        _ => result.push(last),
+                    }
+                }
+            }
+            _ => panic!("oh shit"),
+        }
+    }
+    result
+}
+
+fn vertical(instruction: &Instruction) -> i64 {
+    match instruction.verb {
+        Mouvement::Up => -(instruction.measure as i64),
+        Mouvement::Down => instruction.measure as i64,
+        _ => 0,
+    }
+}
+
+fn push_vertical(result: &mut Vec<Instruction>, net: i64) {
+    if net > 0 {
+        result.push(Instruction::new(Mouvement::Down, net as u32));
+    } else if net < 0 {
+        result.push(Instruction::new(Mouvement::Up, (-net) as u32));
+    }
+}
+
+/// `optimize <part1|part2> [file]`, prints the shorter course.
+pub fn run(args: &[String]) {
+    if args.is_empty() {
+        panic!("usage: optimize <part1|part2> [file]");
+    }
+    let model = Model::parse(&args[0]);
+    let filename = args.get(1).map(|val| val.as_str()).unwrap_or("input");
+    let optimization = optimize(model, &parse(filename));
+
+    if !optimization.is_equivalent() {
+        panic!(
+            "optimized course ends at {:?} instead of {:?}",
+            optimization.optimized_end, optimization.original_end
+        );
+    }
+    for instruction in &optimization.course {
+        println!("{}", instruction);
+    }
+    eprintln!(
+        "{} -> {} instructions ({} removed)",
+        optimization.before,
+        optimization.after,
+        optimization.removed()
+    );
+}
+
+#[cfg(test)]
+mod test_optimizer {
+    use super::*;
+
+    fn course(raw: Vec<&str>) -> Vec<Instruction> {
+        raw.into_iter().map(Instruction::parse).collect()
+    }
+
+    #[test]
+    fn test_optimize_part1() {
+        let instructions = course(vec![
+            "forward 5",
+            "down 5",
+            "forward 8",
+            "up 3",
+            "down 8",
+            "forward 2",
+        ]);
+        let optimization = optimize(Model::Part1, &instructions);
+
+        assert!(optimization.is_equivalent());
+        assert_eq!(optimization.course, course(vec!["forward 15", "down 10"]));
+        assert_eq!(optimization.removed(), 4);
+    }
+
+    #[test]
+    fn test_optimize_part2() {
+        let instructions = course(vec![
+            "forward 5",
+            "down 5",
+            "forward 8",
+            "up 3",
+            "down 8",
+            "forward 2",
+        ]);
+        let optimization = optimize(Model::Part2, &instructions);
+
+        assert!(optimization.is_equivalent());
+        assert_eq!(
+            optimization.course,
+            course(vec![
+                "forward 5",
+                "down 5",
+                "forward 8",
+                "down 5",
+                "forward 2"
+            ])
+        );
+    }
+
+    #[test]
+    fn test_optimize_part2_cancel() {
+        let instructions = course(vec![
+            "down 3",
+            "down 2",
+            "up 1",
+            "forward 0",
+            "forward 2",
+            "down 4",
+            "up 4",
+            "forward 3",
+            "up 4",
+        ]);
+        let optimization = optimize(Model::Part2, &instructions);
+
+        assert!(optimization.is_equivalent());
+        assert_eq!(
+            optimization.course,
+            course(vec!["down 4", "forward 5", "up 4"])
+        );
+        assert_eq!(optimization.before, 9);
+        assert_eq!(optimization.after, 3);
+    }
+
+    #[test]
+    fn test_optimize_input() {
+        let instructions = parse("input");
+        for model in [Model::Part1, Model::Part2] {
+            let optimization = optimize(model, &instructions);
+            assert!(optimization.is_equivalent());
+            assert!(optimization.after <= optimization.before);
+        }
+    }
+}
@@ -0,0 +1,253 @@
+use super::{Aim, Depth, Horizontal, Instruction, Model, Mouvement};
+
+/// Position a planned course must end on. Without an aim any final aim is
+/// accepted.
+#[derive(Debug, Clone, Copy)]
+pub struct Target {
+    pub depth: Depth,
+    pub horizontal: Horizontal,
+    pub aim: Option<Aim>,
+}
+
+impl Target {
+    pub fn new(depth: u32, horizontal: u32, aim: Option<u32>) -> Target {
+        Target {
+            depth: Depth(depth),
+            horizontal: Horizontal(horizontal),
+            aim: aim.map(Aim::new),
+        }
+    }
+}
+
+/// Build a course of minimal length reaching `target` under `model`.
+/// Returns `None` when no course can reach it.
+pub fn plan(model: Model, target: &Target) -> Option<Vec<Instruction>> {
+    match model {
+        Model::Part1 => plan_part1(target),
+        Model::Part2 => plan_part2(target),
+    }
+}
+
+fn plan_part1(target: &Target) -> Option<Vec<Instruction>> {
+    // the aim never moves in this model
+    if let Some(aim) = target.aim {
+        if aim.0 != 0 {
+            return None;
+        }
+    }
+
+    let mut course = Vec::new();
+    if target.horizontal.0 > 0 {
+        course.push(Instruction::new(Mouvement::Forward, target.horizontal.0));
+    }
+    if target.depth.0 > 0 {
+        course.push(Instruction::new(Mouvement::Down, target.depth.0));
+    }
+    Some(course)
+}
+
+/// A minimal course never has two `forward` or two aim changes in a row, so
+/// it is a list of `(aim, forward)` legs plus a last aim change. With `D` the
+/// depth and `H` the horizontal target:
+/// - one leg works when `H` divides `D`, at aim `D / H`,
+/// - two legs, the first at aim 0, work for every divisor `h` of `D` below
+///   `H` and cost at most 4 instructions (`h = 1` always fits),
+/// - three legs or more need at least 5 instructions.
+///
+/// So trying one and two legs is enough to find the shortest course.
+fn plan_part2(target: &Target) -> Option<Vec<Instruction>> {
+    let depth = target.depth.0;
+    let horizontal = target.horizontal.0;
+    let aim = target.aim.map(|val| val.0);
+
+    if horizontal == 0 {
+        if depth != 0 {
+            return None;
+        }
+        return Some(course(&[], aim));
+    }
+
+    let mut candidates: Vec<Vec<Instruction>> = Vec::new();
+    if depth.is_multiple_of(horizontal) {
+        candidates.push(course(&[(depth / horizontal, horizontal)], aim));
+    }
+    for last in divisors(depth) {
+        if last < horizontal {
+            candidates.push(course(&[(0, horizontal - last), (depth / last, last)], aim));
+        }
+    }
+
+    candidates.into_iter().min_by_key(|val| val.len())
+}
+
+/// Emit each `(aim, forward)` leg, changing the aim only when needed, then
+/// move to `final_aim` if one is requested.
+fn course(legs: &[(u32, u32)], final_aim: Option<u32>) -> Vec<Instruction> {
+    let mut result = Vec::new();
+    let mut current = 0;
+    for (aim, forward) in legs {
+        change_aim(&mut result, current, *aim);
+        current = *aim;
+        result.push(Instruction::new(Mouvement::Forward, *forward));
+    }
+    if let Some(aim) = final_aim {
+        change_aim(&mut result, current, aim);
+    }
+    result
+}
+
+fn change_aim(result: &mut Vec<Instruction>, from: u32, to: u32) {
+    if to > from {
+        result.push(Instruction::new(Mouvement::Down, to - from));
+    } else if to < from {
+        result.push(Instruction::new(Mouvement::Up, from - to));
+    }
+}
+
+fn divisors(val: u32) -> Vec<u32> {
+    let mut result = Vec::new();
+    let mut index: u32 = 1;
+    while (index as u64) * (index as u64) <= val as u64 {
+        if val.is_multiple_of(index) {
+            result.push(index);
+            if index != val / index {
+                result.push(val / index);
+            }
+        }
+        index += 1;
+    }
+    result
+}
+
+/// `plan <part1|part2> <depth> <horizontal> [aim]`
+pub fn run(args: &[String]) {
+    if args.len() < 3 {
+        panic!("usage: plan <part1|part2> <depth> <horizontal> [aim]");
+    }
+    let model = Model::parse(&args[0]);
+    let target = Target::new(
+        args[1].parse().unwrap(),
+        args[2].parse().unwrap(),
+        args.get(3).map(|val| val.parse().unwrap()),
+    );
+
+    match plan(model, &target) {
+        Some(course) => {
+            for instruction in course {
+                println!("{}", instruction);
+            }
+        }
+        None => println!("unreachable"),
+    }
+}
+
+#[cfg(test)]
+mod test_planner {
+    use super::*;
+
+    fn check(model: Model, target: &Target, course: &Vec<Instruction>) {
+        let (depth, hor, aim) = model.follow(course);
+        assert_eq!(depth, target.depth);
+        assert_eq!(hor, target.horizontal);
+        if let Some(expected) = target.aim {
+            assert_eq!(aim, expected);
+        }
+    }
+
+    #[test]
+    fn test_plan_part1() {
+        let target = Target::new(10, 15, None);
+        let course = plan(Model::Part1, &target).unwrap();
+        assert_eq!(
+            course,
+            vec![
+                Instruction::parse("forward 15"),
+                Instruction::parse("down 10")
+            ]
+        );
+        check(Model::Part1, &target, &course);
+
+        assert_eq!(plan(Model::Part1, &Target::new(0, 0, None)), Some(vec![]));
+        assert_eq!(plan(Model::Part1, &Target::new(1, 1, Some(3))), None);
+    }
+
+    #[test]
+    fn test_plan_part2() {
+        let target = Target::new(60, 15, Some(10));
+        let course = plan(Model::Part2, &target).unwrap();
+        assert_eq!(course.len(), 3);
+        check(Model::Part2, &target, &course);
+
+        let target = Target::new(7, 3, None);
+        let course = plan(Model::Part2, &target).unwrap();
+        assert_eq!(course.len(), 3);
+        check(Model::Part2, &target, &course);
+
+        let target = Target::new(0, 4, Some(2));
+        let course = plan(Model::Part2, &target).unwrap();
+        assert_eq!(course.len(), 2);
+        check(Model::Part2, &target, &course);
+    }
+
+    #[test]
+    fn test_plan_part2_unreachable() {
+        assert_eq!(plan(Model::Part2, &Target::new(5, 0, None)), None);
+        assert_eq!(
+            plan(Model::Part2, &Target::new(0, 0, Some(3))),
+            Some(vec![Instruction::parse("down 3")])
+        );
+    }
+
+    /// Compare with every course of up to 3 instructions on small values.
+    #[test]
+    fn test_plan_part2_is_minimal() {
+        let mut courses: Vec<Vec<Instruction>> = vec![vec![]];
+        let mut shortest: Vec<((u32, u32, u32), usize)> = Vec::new();
+        for length in 1..=3 {
+            let mut next = Vec::new();
+            for prefix in &courses {
+                for verb in [Mouvement::Forward, Mouvement::Up, Mouvement::Down] {
+                    for measure in 1..=7 {
+                        let mut current = prefix.clone();
+                        current.push(Instruction::new(verb, measure));
+                        next.push(current);
+                    }
+                }
+            }
+            for current in &next {
+                // skip courses where the aim would go below 0
+                let mut aim: i64 = 0;
+                if current.iter().any(|val| {
+                    match val.verb {
+                        Mouvement::Up => aim -= val.measure as i64,
+                        Mouvement::Down => aim += val.measure as i64,
+                        _ => (),
+                    };
+                    aim < 0
+                }) {
+                    continue;
+                }
+                let (depth, hor, aim) = Model::Part2.follow(current);
+                let key = (depth.0, hor.0, aim.0);
+                if !shortest.iter().any(|(val, _)| *val == key) {
+                    shortest.push((key, length));
+                }
+            }
+            courses = next;
+        }
+
+        for depth in 1..8 {
+            for hor in 1..5 {
+                for aim in 0..4 {
+                    let target = Target::new(depth, hor, Some(aim));
+                    let course = plan(Model::Part2, &target).unwrap();
+                    check(Model::Part2, &target, &course);
+                    match shortest.iter().find(|(val, _)| *val == (depth, hor, aim)) {
+                        Some((_, length)) => assert_eq!(course.len(), *length),
+                        None => assert_eq!(course.len(), 4),
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,360 @@
+use std::fs;
+use std::io::{self, Write};
+
+use super::{report, BitNumber, ConvertError, MAX_WIDTH, WORDS};
+
+/// Encoding of the rows of a report.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Format {
+    /// One line of `0`/`1` per row, the puzzle format.
+    Binary,
+    /// One line of hex digits per row, on the given width or 4 bits per
+    /// digit without one.
+    Hex(Option<usize>),
+    /// One decimal integer per line, on the given width.
+    Decimal(usize),
+    /// Raw big endian records of `width / 8` bytes rounded up.
+    Packed(usize),
+}
+
+impl Format {
+    /// `binary`, `hex`, `hex:<width>`, `decimal:<width>` or
+    /// `packed:<width>`.
+    pub fn parse(raw: &str) -> Format {
+        let width = |val: &str| {
+            let width = val.parse().unwrap();
+            check_width(width).unwrap_or_else(|err| panic!("{}: {}", raw, err));
+            width
+        };
+        match raw.split_once(':') {
+            None if raw == "binary" => Format::Binary,
+            None if raw == "hex" => Format::Hex(None),
+            Some(("hex", val)) => Format::Hex(Some(width(val))),
+            Some(("decimal", val)) => Format::Decimal(width(val)),
+            Some(("packed", val)) => Format::Packed(width(val)),
+            _ => panic!("unknown format {}", raw),
+        }
+    }
+
+    /// Binary when every line is made of `0`/`1`, hex when they are all hex
+    /// digits with at least one letter: digits only could be decimal too.
+    /// Decimal and packed input carry no width and can't be guessed.
+    pub fn detect(content: &[u8]) -> Option<Format> {
+        let text = std::str::from_utf8(content).ok()?;
+        let lines: Vec<&str> = text.lines().collect();
+        if lines.is_empty() {
+            return None;
+        }
+        if lines
+            .iter()
+            .all(|line| !line.is_empty() && line.chars().all(|val| matches!(val, '0' | '1' | '?')))
+        {
+            return Some(Format::Binary);
+        }
+        if lines
+            .iter()
+            .all(|line| !line.is_empty() && line.chars().all(|val| val.is_ascii_hexdigit()))
+            && text.chars().any(|val| val.is_ascii_alphabetic())
+        {
+            return Some(Format::Hex(None));
+        }
+        None
+    }
+
+    fn record_size(width: usize) -> usize {
+        width.div_ceil(8)
+    }
+
+    /// Read every row, the errors name the line (or record) starting at 1.
+    pub fn decode(&self, content: &[u8]) -> Result<Vec<BitNumber>, String> {
+        if let Format::Packed(width) = self {
+            check_width(*width)?;
+            let size = Format::record_size(*width);
+            if !content.len().is_multiple_of(size) {
+                return Err(format!(
+                    "{} bytes is not a multiple of the {} bytes records",
+                    content.len(),
+                    size
+                ));
+            }
+            return content
+                .chunks(size)
+                .enumerate()
+                .map(|(index, record)| {
+                    let mut words = [0; WORDS];
+                    for byte in record {
+                        words = multiply_add(&words, 256, *byte as u64)
+                            .ok_or_else(|| format!("record {}: too large", index + 1))?;
+                    }
+                    from_words(words, *width)
+                        .map_err(|err| format!("record {}: {}", index + 1, err))
+                })
+                .collect();
+        }
+
+        let text = std::str::from_utf8(content).map_err(|err| err.to_string())?;
+        text.lines()
+            .enumerate()
+            .map(|(index, line)| {
+                self.decode_line(line.trim())
+                    .map_err(|err| format!("line {}: {}", index + 1, err))
+            })
+            .collect()
+    }
+
+    fn decode_line(&self, line: &str) -> Result<BitNumber, String> {
+        if line.is_empty() {
+            return Err(String::from("empty line"));
+        }
+        match self {
+            Format::Binary => {
+                check_width(line.len())?;
+                Ok(BitNumber::new(line))
+            }
+            Format::Hex(width) => {
+                let width = width.unwrap_or(line.len() * 4);
+                check_width(width)?;
+                let mut words = [0; WORDS];
+                for digit in line.chars() {
+                    let digit = digit
+                        .to_digit(16)
+                        .ok_or_else(|| format!("'{}' is not a hex digit", digit))?;
+                    words = multiply_add(&words, 16, digit as u64)
+                        .ok_or_else(|| format!("{} doesn't fit in {} bits", line, width))?;
+                }
+                from_words(words, width)
+            }
+            Format::Decimal(width) => {
+                check_width(*width)?;
+                let mut words = [0; WORDS];
+                for digit in line.chars() {
+                    let digit = digit
+                        .to_digit(10)
+                        .ok_or_else(|| format!("'{}' is not a decimal digit", digit))?;
+                    words = multiply_add(&words, 10, digit as u64)
+                        .ok_or_else(|| format!("{} doesn't fit in {} bits", line, width))?;
+                }
+                from_words(words, *width)
+            }
+            Format::Packed(_) => unreachable!(),
+        }
+    }
+
+    /// Write `number` in this format, text formats end with a new line.
+    pub fn encode(&self, number: &BitNumber) -> Result<Vec<u8>, ConvertError> {
+        if let Format::Binary = self {
+            return Ok(format!("{}\n", number).into_bytes());
+        }
+        number.try_to_wide()?;
+        match self {
+            Format::Hex(_) => {
+                let digits = number.size().div_ceil(4);
+                let mut result = String::new();
+                for digit in (0..digits).rev() {
+                    let (word, shift) = (digit * 4 / 64, digit * 4 % 64);
+                    let val = (number.value[word] >> shift) & 0xf;
+                    result.push(char::from_digit(val as u32, 16).unwrap());
+                }
+                result.push('\n');
+                Ok(result.into_bytes())
+            }
+            Format::Decimal(_) => Ok(format!("{}\n", number.to_wide()).into_bytes()),
+            Format::Packed(_) => {
+                let size = Format::record_size(number.size());
+                Ok((0..size)
+                    .rev()
+                    .map(|byte| (number.value[byte / 8] >> (byte % 8 * 8)) as u8)
+                    .collect())
+            }
+            Format::Binary => unreachable!(),
+        }
+    }
+}
+
+fn check_width(width: usize) -> Result<(), String> {
+    if width == 0 {
+        return Err(String::from("a width of 0 bits holds nothing"));
+    }
+    if width > MAX_WIDTH {
+        return Err(format!("{} bits don't fit in a BitNumber", width));
+    }
+    Ok(())
+}
+
+/// `words * factor + digit`, `None` on overflow.
+fn multiply_add(words: &[u64; WORDS], factor: u64, digit: u64) -> Option<[u64; WORDS]> {
+    let mut result = [0; WORDS];
+    let mut carry = digit as u128;
+    for (index, word) in words.iter().enumerate() {
+        let current = *word as u128 * factor as u128 + carry;
+        result[index] = current as u64;
+        carry = current >> 64;
+    }
+    if carry != 0 {
+        return None;
+    }
+    Some(result)
+}
+
+fn from_words(words: [u64; WORDS], width: usize) -> Result<BitNumber, String> {
+    let number = BitNumber::with_value(words, width);
+    for (word, value) in words.iter().enumerate() {
+        if value & !number.full(word) != 0 {
+            return Err(format!("value doesn't fit in {} bits", width));
+        }
+    }
+    Ok(number)
+}
+
+/// Read `filename` in `format`, or the detected one when `None`.
+pub fn read(filename: &str, format: Option<Format>) -> Result<Vec<BitNumber>, String> {
+    let content = fs::read(filename).map_err(|err| format!("{}: {}", filename, err))?;
+    let format = match format {
+        Some(format) => format,
+        None => Format::detect(&content).ok_or_else(|| {
+            format!(
+                "{}: can't detect the format, set it with --input=",
+                filename
+            )
+        })?,
+    };
+    format.decode(&content)
+}
+
+/// `rates [--input=<format>] [--output=<format>] [--pad] [--strict] [file]`,
+/// writes the gamma then the epsilon rate.
+pub fn run(args: &[String]) {
+    let mut filename = "input";
+    let mut input = None;
+    let mut output = Format::Binary;
+    for arg in args {
+        match arg.split_once('=') {
+            Some(("--input", val)) => input = Some(Format::parse(val)),
+            Some(("--output", val)) => output = Format::parse(val),
+            _ if arg.starts_with("--") => (),
+            _ => filename = arg,
+        }
+    }
+
+    let report = report(
+        read(filename, input).unwrap_or_else(|err| panic!("{}", err)),
+        args,
+    );
+    let mut stdout = io::stdout();
+    for rate in [report.gamma_rate(), report.epsilon_rate()] {
+        let bytes = output.encode(&rate).unwrap_or_else(|err| panic!("{}", err));
+        stdout.write_all(&bytes).unwrap();
+    }
+}
+
+#[cfg(test)]
+mod test_format {
+    use super::*;
+
+    fn rows(raw: Vec<&str>) -> Vec<BitNumber> {
+        raw.into_iter().map(BitNumber::new).collect()
+    }
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(Format::detect(b"0101\n1100\n"), Some(Format::Binary));
+        assert_eq!(Format::detect(b"0a1\nff0\n"), Some(Format::Hex(None)));
+        assert_eq!(Format::detect(b"175\n3841\n"), None);
+        assert_eq!(Format::detect(b"12\nzz\n"), None);
+        assert_eq!(Format::detect(&[0xff, 0x00, 0xfe]), None);
+    }
+
+    #[test]
+    fn test_decode() {
+        let expected = rows(vec!["000010101111", "111100000001"]);
+
+        assert_eq!(
+            Format::Binary.decode(b"000010101111\n111100000001\n"),
+            Ok(expected.clone())
+        );
+        assert_eq!(
+            Format::Hex(None).decode(b"0AF\nf01\n"),
+            Ok(expected.clone())
+        );
+        assert_eq!(
+            Format::Decimal(12).decode(b"175\n3841\n"),
+            Ok(expected.clone())
+        );
+        assert_eq!(
+            Format::Packed(12).decode(&[0x00, 0xaf, 0x0f, 0x01]),
+            Ok(expected)
+        );
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        assert_eq!(
+            Format::Decimal(4).decode(b"3\n16\n"),
+            Err(String::from("line 2: value doesn't fit in 4 bits"))
+        );
+        assert_eq!(
+            Format::Decimal(4).decode(b"3\n\n5\n"),
+            Err(String::from("line 2: empty line"))
+        );
+        assert_eq!(
+            Format::Hex(None).decode(b"0g\n"),
+            Err(String::from("line 1: 'g' is not a hex digit"))
+        );
+        assert_eq!(
+            Format::Hex(Some(5)).decode(b"1f\n20\n"),
+            Err(String::from("line 2: value doesn't fit in 5 bits"))
+        );
+        assert!(Format::Packed(12).decode(&[0x00, 0xaf, 0x0f]).is_err());
+        assert_eq!(
+            Format::Packed(0).decode(b""),
+            Err(String::from("a width of 0 bits holds nothing"))
+        );
+        assert_eq!(
+            Format::Packed(12).decode(&[0x10, 0x00]),
+            Err(String::from("record 1: value doesn't fit in 12 bits"))
+        );
+    }
+
+    #[test]
+    fn test_encode() {
+        let number = BitNumber::new("000010101111");
+
+        assert_eq!(
+            Format::Binary.encode(&number),
+            Ok(b"000010101111\n".to_vec())
+        );
+        assert_eq!(Format::Hex(None).encode(&number), Ok(b"0af\n".to_vec()));
+        assert_eq!(Format::Decimal(12).encode(&number), Ok(b"175\n".to_vec()));
+        assert_eq!(Format::Packed(12).encode(&number), Ok(vec![0x00, 0xaf]));
+
+        assert!(Format::Hex(None).encode(&BitNumber::new("1?")).is_err());
+        assert_eq!(
+            Format::Binary.encode(&BitNumber::new("1?")),
+            Ok(b"1?\n".to_vec())
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_parse_no_width() {
+        Format::parse("packed:0");
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for width in [5, 200] {
+            let numbers = rows(vec![&"1".repeat(width), &"0".repeat(width)]);
+            for format in [
+                Format::Hex(Some(width)),
+                Format::Decimal(width),
+                Format::Packed(width),
+            ] {
+                let mut content = Vec::new();
+                for number in &numbers {
+                    content.append(&mut format.encode(number).unwrap());
+                }
+                assert_eq!(format.decode(&content), Ok(numbers.clone()));
+            }
+        }
+    }
+}
@@ -1,4 +1,16 @@
-use std::fs;
+use std::cmp::Ordering;
+use std::env;
+use std::fmt;
+
+mod format;
+mod ops;
+mod stats;
+mod trie;
+mod wide;
+
+use format::Format;
+use trie::Trie;
+use wide::Wide;
 
 #[derive(Debug, PartialEq, Clone, Copy)]
 enum Bit {
@@ -7,56 +19,274 @@ enum Bit {
     Unknown,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+/// Widest `BitNumber` supported.
+const MAX_WIDTH: usize = WORDS * 64;
+const WORDS: usize = 4;
+
+/// Fixed width number, bit 0 is the most significant one. The bits are
+/// packed in little endian `u64` words with a second set of words marking the
+/// unknown ones.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 struct BitNumber {
-    pub data: Vec<Bit>,
+    value: [u64; WORDS],
+    unknown: [u64; WORDS],
+    width: usize,
+}
+
+/// Why a `BitNumber` can't be turned into an integer.
+#[derive(Debug, PartialEq)]
+enum ConvertError {
+    /// The number doesn't fit in `target` bits.
+    Overflow { width: usize, target: usize },
+    /// The bit at `index` is unknown.
+    Unknown { index: usize },
+}
+
+impl fmt::Display for ConvertError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConvertError::Overflow { width, target } => {
+                write!(f, "{} bits number doesn't fit in {} bits", width, target)
+            }
+            ConvertError::Unknown { index } => write!(f, "bit {} is unknown", index),
+        }
+    }
 }
 
 impl BitNumber {
     pub fn new(raw: &str) -> BitNumber {
-        let tmp: Vec<char> = raw.chars().collect();
-        let mut data: Vec<Bit> = Vec::new();
-        for current in tmp.iter() {
-            let val = match current {
-                '1' => Bit::One,
-                '0' => Bit::Zero,
-                _ => Bit::Unknown,
-            };
-            data.push(val);
+        let width = raw.chars().count();
+        assert!(
+            width <= MAX_WIDTH,
+            "{} bits don't fit in a BitNumber",
+            width
+        );
+        let mut result = BitNumber::with_value([0; WORDS], width);
+        for (index, current) in raw.chars().enumerate() {
+            let (word, mask) = result.mask(index);
+            match current {
+                '1' => result.value[word] |= mask,
+                '0' => (),
+                _ => result.unknown[word] |= mask,
+            }
         }
-        BitNumber { data: data }
+        result
     }
+
+    fn with_value(value: [u64; WORDS], width: usize) -> BitNumber {
+        BitNumber {
+            value,
+            unknown: [0; WORDS],
+            width,
+        }
+    }
+
     pub fn size(&self) -> usize {
-        self.data.len()
+        self.width
+    }
+
+    /// Bits of `word` inside the width.
+    fn full(&self, word: usize) -> u64 {
+        let bits = self.width.saturating_sub(word * 64).min(64) as u32;
+        u64::MAX.checked_shr(64 - bits).unwrap_or(0)
+    }
+
+    fn mask(&self, index: usize) -> (usize, u64) {
+        let shift = self.width - 1 - index;
+        (shift / 64, 1 << (shift % 64))
+    }
+
+    pub fn bit(&self, index: usize) -> Bit {
+        let (word, mask) = self.mask(index);
+        if self.unknown[word] & mask != 0 {
+            Bit::Unknown
+        } else if self.value[word] & mask != 0 {
+            Bit::One
+        } else {
+            Bit::Zero
+        }
+    }
+
+    pub fn set(&mut self, index: usize, bit: Bit) {
+        let (word, mask) = self.mask(index);
+        self.value[word] &= !mask;
+        self.unknown[word] &= !mask;
+        match bit {
+            Bit::One => self.value[word] |= mask,
+            Bit::Zero => (),
+            Bit::Unknown => self.unknown[word] |= mask,
+        }
     }
 
+    /// Unknown bits stay unknown.
     pub fn invert(&self) -> BitNumber {
-	let mut tmp: Vec<Bit> = Vec::new();
-	for val in &self.data {
-	    if *val == Bit::One {
-		tmp.push(Bit::Zero);
-	    } else {
-		tmp.push(Bit::One);
-	    }
-	}
-	BitNumber{data: tmp}
+        let mut value = [0; WORDS];
+        for (word, current) in value.iter_mut().enumerate() {
+            *current = !(self.value[word] | self.unknown[word]) & self.full(word);
+        }
+        BitNumber {
+            value,
+            unknown: self.unknown,
+            width: self.width,
+        }
     }
 
-    pub fn to_u64(&self) -> u64 {
-	let mut result:u64 = 0;
-	for (index, bit) in self.data.iter().rev().enumerate() {
-	    if *bit==Bit::One {
-		result = result + (2u64.pow(index.try_into().unwrap()));
-	    }
-	}
-	result
+    /// Index of the first unknown bit.
+    pub fn first_unknown(&self) -> Option<usize> {
+        (0..self.width).find(|index| self.bit(*index) == Bit::Unknown)
+    }
+
+    /// Works for every width, panics when a bit is unknown.
+    pub fn to_wide(&self) -> Wide {
+        self.try_to_wide().unwrap()
+    }
+
+    pub fn try_to_wide(&self) -> Result<Wide, ConvertError> {
+        self.check_known()?;
+        Ok(Wide::new(&self.value))
+    }
+
+    fn check_known(&self) -> Result<(), ConvertError> {
+        match self.first_unknown() {
+            Some(index) => Err(ConvertError::Unknown { index }),
+            None => Ok(()),
+        }
     }
 
     pub fn is_this_byte_one(&self, index: usize) -> bool {
-	self.data[index] == Bit::One
+        self.bit(index) == Bit::One
+    }
+}
+
+/// Conversions to the native integers, the rates themselves go through
+/// `Wide`.
+#[allow(dead_code)]
+impl BitNumber {
+    /// Panics when a bit above 64 is set or a bit is unknown, see
+    /// `try_to_u64`.
+    pub fn to_u64(&self) -> u64 {
+        self.try_to_u64().unwrap()
+    }
+
+    pub fn try_to_u64(&self) -> Result<u64, ConvertError> {
+        u64::try_from(self.try_to_u128()?).map_err(|_| ConvertError::Overflow {
+            width: self.width,
+            target: 64,
+        })
+    }
+
+    /// Panics when a bit above 128 is set or a bit is unknown, see
+    /// `try_to_u128`.
+    pub fn to_u128(&self) -> u128 {
+        self.try_to_u128().unwrap()
+    }
+
+    pub fn try_to_u128(&self) -> Result<u128, ConvertError> {
+        self.check_known()?;
+        if self.value[2..].iter().any(|val| *val != 0) {
+            return Err(ConvertError::Overflow {
+                width: self.width,
+                target: 128,
+            });
+        }
+        Ok(((self.value[1] as u128) << 64) | self.value[0] as u128)
     }
 }
 
+/// How `gamma_rate` picks the bit of a column: 1 when the share of ones is
+/// above `threshold`, 0 when it is below and `tie` when it is exactly on it.
+#[derive(Debug, Clone, Copy)]
+struct Majority {
+    /// Share of ones as `(numerator, denominator)`.
+    threshold: (usize, usize),
+    tie: Bit,
+}
+
+impl Default for Majority {
+    /// Half of the rows, ties go to 0.
+    fn default() -> Majority {
+        Majority {
+            threshold: (1, 2),
+            tie: Bit::Zero,
+        }
+    }
+}
+
+impl Majority {
+    /// Compare `ones / rows` with the threshold.
+    fn compare(&self, ones: usize, rows: usize) -> Ordering {
+        let (numerator, denominator) = self.threshold;
+        (ones * denominator).cmp(&(rows * numerator))
+    }
+}
+
+#[derive(Debug)]
+struct GammaRate {
+    rate: BitNumber,
+    /// Columns where the share of ones was exactly the threshold.
+    ties: Vec<usize>,
+}
+
+/// What a report accepts as unknown bits.
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Mode {
+    /// Any unknown bit is an error.
+    Strict,
+    /// Unknown bits are left out of the column counts and match anything in
+    /// the filters.
+    Masked,
+}
+
+/// What `Report::validated` does with rows narrower than the widest one.
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Padding {
+    /// Every row must have the width of the first one.
+    Reject,
+    /// Short rows get leading zeros up to the widest row.
+    Left,
+}
+
+/// Why rows can't make a report, lines and positions start at 1.
+#[derive(Debug, PartialEq)]
+enum ReportError {
+    Empty,
+    Ragged {
+        line: usize,
+        width: usize,
+        expected: usize,
+    },
+    /// An unknown bit in strict mode.
+    Unknown {
+        line: usize,
+        position: usize,
+    },
+}
+
+impl fmt::Display for ReportError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReportError::Empty => write!(f, "empty report"),
+            ReportError::Ragged {
+                line,
+                width,
+                expected,
+            } => write!(f, "line {}: {} bits instead of {}", line, width, expected),
+            ReportError::Unknown { line, position } => {
+                write!(f, "line {} position {}: unknown bit", line, position)
+            }
+        }
+    }
+}
+
+/// How the oxygen and CO2 ratings filter the rows.
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Backend {
+    /// Copy the matching rows at each position.
+    Filter,
+    /// Walk down a `Trie` of the rows.
+    Trie,
+}
+
 struct Report(Vec<BitNumber>);
 
 impl Report {
@@ -64,92 +294,258 @@ impl Report {
         Report(rows)
     }
 
+    /// Check that there is at least one row, that they all have the same
+    /// width, or pad them to it, and that `mode` accepts their unknown bits.
+    fn validated(
+        rows: Vec<BitNumber>,
+        padding: Padding,
+        mode: Mode,
+    ) -> Result<Report, ReportError> {
+        let expected = match padding {
+            Padding::Reject => rows.first().map(|row| row.size()),
+            Padding::Left => rows.iter().map(|row| row.size()).max(),
+        }
+        .ok_or(ReportError::Empty)?;
+
+        let rows = rows
+            .into_iter()
+            .enumerate()
+            .map(|(line, row)| match padding {
+                Padding::Reject if row.size() != expected => Err(ReportError::Ragged {
+                    line: line + 1,
+                    width: row.size(),
+                    expected,
+                }),
+                // bit 0 is the most significant one, the words don't move
+                Padding::Left => Ok(BitNumber {
+                    width: expected,
+                    ..row
+                }),
+                Padding::Reject => Ok(row),
+            })
+            .collect::<Result<Vec<BitNumber>, ReportError>>()?;
+
+        if mode == Mode::Strict {
+            for (line, row) in rows.iter().enumerate() {
+                if let Some(index) = row.first_unknown() {
+                    return Err(ReportError::Unknown {
+                        line: line + 1,
+                        position: index + 1,
+                    });
+                }
+            }
+        }
+        Ok(Report::new(rows))
+    }
+
     fn size_row(&self) -> usize {
         self.0.len()
     }
 
+    /// Width of the rows, 0 for an empty report.
     fn size_column(&self) -> usize {
-        self.0[0].size()
+        self.0.first().map(|row| row.size()).unwrap_or(0)
     }
 
-    fn gamma_rate(&self) -> BitNumber {
+    /// Number of ones in each column.
+    fn count_ones(&self) -> Vec<usize> {
+        self.count_columns(|row| &row.value)
+    }
+
+    /// Number of unknown bits in each column.
+    fn count_unknowns(&self) -> Vec<usize> {
+        self.count_columns(|row| &row.unknown)
+    }
+
+    fn count_columns(&self, words: fn(&BitNumber) -> &[u64; WORDS]) -> Vec<usize> {
         let number_column = self.size_column();
 
-        let mut tmp: Vec<char> = Vec::new();
-        for y in 0..number_column {
-	    let foo = self
-                .0
-                .iter()
-                .map(|val| val.data[y].clone())
-                .collect::<Vec<Bit>>();
-
-            let mut count = 0;
-	    for x in &foo {
-		if *x == Bit::One {
-		    count +=1;
-		}
-	    }
-
-            if count <= foo.len() / 2 {
-                tmp.push('0');
-            } else {
-                tmp.push('1');
+        // shift 0 is the last column
+        let mut counts = vec![0; number_column];
+        for row in &self.0 {
+            for (word, value) in words(row).iter().enumerate() {
+                let mut ones = *value;
+                while ones != 0 {
+                    counts[word * 64 + ones.trailing_zeros() as usize] += 1;
+                    ones &= ones - 1;
+                }
             }
         }
+        counts.reverse();
+        counts
+    }
 
-        BitNumber::new(String::from_iter(tmp).as_str())
+    /// Strict majority of ones, ties go to 0. Unknown bits are left out of
+    /// the count of their column.
+    fn gamma_rate(&self) -> BitNumber {
+        self.gamma_rate_with(&Majority::default()).rate
+    }
+
+    fn gamma_rate_with(&self, majority: &Majority) -> GammaRate {
+        let mut rate = BitNumber::with_value([0; WORDS], self.size_column());
+        let mut ties = Vec::new();
+        let unknowns = self.count_unknowns();
+        for (index, count) in self.count_ones().iter().enumerate() {
+            let known = self.size_row() - unknowns[index];
+            let bit = match majority.compare(*count, known) {
+                Ordering::Greater => Bit::One,
+                Ordering::Less => Bit::Zero,
+                Ordering::Equal => {
+                    ties.push(index);
+                    majority.tie
+                }
+            };
+            rate.set(index, bit);
+        }
+
+        GammaRate { rate, ties }
     }
 
     fn epsilon_rate(&self) -> BitNumber {
-	self.gamma_rate().invert()
+        self.gamma_rate().invert()
     }
 
+    /// Keep the rows with `val` at `index`, an unknown bit matches both.
     fn filter_on_index(input: Vec<BitNumber>, index: usize, val: Bit) -> Vec<BitNumber> {
-	let mut output: Vec<BitNumber> = Vec::new();
-	for current in input {
-	    if val == Bit::One {
-		if current.is_this_byte_one(index) {
-		    output.push(current.clone());
-		}
-	    } else {
-		if !current.is_this_byte_one(index) {
-		    output.push(current.clone());
-		}
-	    }
-	}
-	output
+        input
+            .into_iter()
+            .filter(|current| {
+                let bit = current.bit(index);
+                bit == val || bit == Bit::Unknown
+            })
+            .collect()
+    }
+
+    /// Keep the rows matching the bit criteria, recomputed on the remaining
+    /// rows at each position, until only one is left. Unknown bits are left
+    /// out of the counts and match any criteria. A criteria picking a bit no
+    /// row has keeps the other one. When several rows are still left at the
+    /// end they only differ on unknown bits, the result is then the bits
+    /// picked by the criteria.
+    fn rate(&self, criteria: fn(usize, usize) -> Bit) -> BitNumber {
+        let mut input = self.0.clone();
+        let mut picked = BitNumber::with_value([0; WORDS], self.size_column());
+        for index in 0..self.size_column() {
+            if input.len() == 1 {
+                return input[0].clone();
+            }
+            let ones = input
+                .iter()
+                .filter(|val| val.is_this_byte_one(index))
+                .count();
+            let zeros = input
+                .iter()
+                .filter(|val| val.bit(index) == Bit::Zero)
+                .count();
+            let bit = match criteria(ones, zeros) {
+                // the criteria can't drop every row, keep the only bit left
+                Bit::One if zeros == input.len() => Bit::Zero,
+                Bit::Zero if ones == input.len() => Bit::One,
+                bit => bit,
+            };
+            picked.set(index, bit);
+            input = Report::filter_on_index(input, index, bit);
+        }
+
+        if input.len() == 1 {
+            return input[0].clone();
+        }
+        picked
+    }
+
+    /// Run the bit criteria on `backend`, the trie needs rows without
+    /// unknown bits and falls back to filtering otherwise.
+    fn rate_with(&self, criteria: fn(usize, usize) -> Bit, backend: Backend) -> BitNumber {
+        match backend {
+            Backend::Filter => self.rate(criteria),
+            Backend::Trie => match Trie::new(self) {
+                Some(trie) => trie.rate(criteria),
+                None => self.rate(criteria),
+            },
+        }
+    }
+
+    fn oxygen_rate(&self, backend: Backend) -> BitNumber {
+        self.rate_with(most_common, backend)
     }
 
-    fn oxygen_rate(&self) -> BitNumber {
+    fn co2_rate(&self, backend: Backend) -> BitNumber {
+        self.rate_with(least_common, backend)
+    }
+
+    fn power_consumption(&self) -> Wide {
+        self.gamma_rate().to_wide() * self.epsilon_rate().to_wide()
+    }
 
-	let gamma = self.gamma_rate();
-	let mut input = self.0.clone();
-	for (index, bit) in gamma.data.iter().enumerate() {
-	    input = Report::filter_on_index(input, index, *bit);
-	    println!("input : {:?}", input);
+    /// Fails when a rating kept an unknown bit.
+    fn life_support_rate(&self, backend: Backend) -> Result<Wide, ConvertError> {
+        Ok(self.oxygen_rate(backend).try_to_wide()? * self.co2_rate(backend).try_to_wide()?)
+    }
+}
 
-	}
+/// Oxygen criteria: most common bit, ties keep the 1.
+fn most_common(ones: usize, zeros: usize) -> Bit {
+    if ones >= zeros {
+        Bit::One
+    } else {
+        Bit::Zero
+    }
+}
 
-	input[0].clone()
+/// CO2 criteria: least common bit, ties keep the 0.
+fn least_common(ones: usize, zeros: usize) -> Bit {
+    if zeros <= ones {
+        Bit::Zero
+    } else {
+        Bit::One
     }
 }
 
-fn parse(filename: &str) -> Vec<BitNumber> {
-    let content = fs::read_to_string(filename).expect("can't read input");
-    content
-        .lines()
-        .into_iter()
-        .map(|value| BitNumber::new(value))
-        .collect()
+/// Report of `numbers` checked as the `--pad` and `--strict` flags of `args`
+/// say, panics when the rows are rejected.
+fn report(numbers: Vec<BitNumber>, args: &[String]) -> Report {
+    let padding = if args.iter().any(|val| val == "--pad") {
+        Padding::Left
+    } else {
+        Padding::Reject
+    };
+    let mode = if args.iter().any(|val| val == "--strict") {
+        Mode::Strict
+    } else {
+        Mode::Masked
+    };
+    Report::validated(numbers, padding, mode).unwrap_or_else(|err| panic!("{}", err))
 }
 
 fn main() {
-    let numbers = parse("input");
-    let report = Report::new(numbers);
-    let gamma = report.gamma_rate();
-    let epsilon = report.epsilon_rate();
+    let args: Vec<String> = env::args().collect();
+    if args.get(1).map(|val| val.as_str()) == Some("stats") {
+        return stats::run(&args[2..]);
+    }
+    if args.get(1).map(|val| val.as_str()) == Some("rates") {
+        return format::run(&args[2..]);
+    }
 
-    println!("part 1 : {}", gamma.to_u64() * epsilon.to_u64());
+    let input = args
+        .iter()
+        .find_map(|val| val.strip_prefix("--input="))
+        .map(Format::parse);
+    let numbers = format::read("input", input).unwrap_or_else(|err| panic!("{}", err));
+    let report = report(numbers, &args);
+    let gamma = report.gamma_rate_with(&Majority::default());
+    if !gamma.ties.is_empty() {
+        println!("gamma rate ties on columns {:?}", gamma.ties);
+    }
+    println!("part 1 : {}", report.power_consumption());
+    let backend = if args.iter().any(|val| val == "--trie") {
+        Backend::Trie
+    } else {
+        Backend::Filter
+    };
+    match report.life_support_rate(backend) {
+        Ok(rate) => println!("part 2 : {}", rate),
+        Err(err) => println!("part 2 : {}", err),
+    }
 }
 
 #[cfg(test)]
@@ -159,12 +555,12 @@ mod test_bitnumber {
     #[test]
     fn test_new() {
         let bits = BitNumber::new("01010");
-        assert_eq!(bits.data[0], Bit::Zero);
-        assert_eq!(bits.data[1], Bit::One);
+        assert_eq!(bits.bit(0), Bit::Zero);
+        assert_eq!(bits.bit(1), Bit::One);
 
         let bits = BitNumber::new("010101010101101011010101");
-        assert_eq!(bits.data[0], Bit::Zero);
-        assert_eq!(bits.data[1], Bit::One);
+        assert_eq!(bits.bit(0), Bit::Zero);
+        assert_eq!(bits.bit(1), Bit::One);
     }
 
     #[test]
@@ -173,6 +569,18 @@ mod test_bitnumber {
         assert_eq!(bits.size(), 5);
     }
 
+    #[test]
+    fn test_bit() {
+        let bits = BitNumber::new("01?");
+        assert_eq!(bits.bit(0), Bit::Zero);
+        assert_eq!(bits.bit(1), Bit::One);
+        assert_eq!(bits.bit(2), Bit::Unknown);
+        assert_eq!(bits.invert(), BitNumber::new("10?"));
+        assert_eq!(bits.first_unknown(), Some(2));
+        assert_eq!(bits.try_to_u64(), Err(ConvertError::Unknown { index: 2 }));
+        assert!(bits.try_to_wide().is_err());
+    }
+
     #[test]
     fn test_invert() {
 	let bits = BitNumber::new("01010");
@@ -199,6 +607,35 @@ mod test_bitnumber {
 	assert_eq!(bits.to_u64(), 9);
     }
 
+    #[test]
+    fn test_wide() {
+        let raw = "1".repeat(100);
+        let bits = BitNumber::new(&raw);
+        assert_eq!(bits.size(), 100);
+        assert_eq!(bits.to_u128(), (1u128 << 100) - 1);
+        assert_eq!(
+            bits.try_to_u64(),
+            Err(ConvertError::Overflow {
+                width: 100,
+                target: 64
+            })
+        );
+        assert!(bits.invert().to_wide().is_zero());
+
+        let bits = BitNumber::new(&format!("1{}", "0".repeat(255)));
+        assert!(bits.is_this_byte_one(0));
+        assert!(!bits.is_this_byte_one(255));
+        assert!(bits.try_to_u128().is_err());
+        assert_eq!(
+            bits.to_wide().to_string(),
+            "57896044618658097711785492504343953926634992332820282019728792003956564819968"
+        );
+
+        // leading zeros don't overflow
+        let bits = BitNumber::new(&format!("{}101", "0".repeat(150)));
+        assert_eq!(bits.try_to_u64(), Ok(5));
+    }
+
     #[test]
     fn test_is_byte_one() {
 	let bits = BitNumber::new("01001");
@@ -233,6 +670,161 @@ mod test_report {
         assert_eq!(report.gamma_rate(), BitNumber::new("10"));
     }
 
+    #[test]
+    fn test_gamma_rate_ties() {
+        let report = Report::new(vec![
+            BitNumber::new("011"),
+            BitNumber::new("100"),
+            BitNumber::new("101"),
+            BitNumber::new("010"),
+        ]);
+
+        let gamma = report.gamma_rate_with(&Majority::default());
+        assert_eq!(gamma.rate, BitNumber::new("000"));
+        assert_eq!(gamma.ties, vec![0, 1, 2]);
+        assert_eq!(report.gamma_rate(), gamma.rate);
+
+        let majority = Majority {
+            tie: Bit::One,
+            ..Majority::default()
+        };
+        assert_eq!(
+            report.gamma_rate_with(&majority).rate,
+            BitNumber::new("111")
+        );
+
+        let majority = Majority {
+            tie: Bit::Unknown,
+            ..Majority::default()
+        };
+        let gamma = report.gamma_rate_with(&majority);
+        assert_eq!(gamma.rate.bit(1), Bit::Unknown);
+        assert_eq!(gamma.rate, BitNumber::new("???"));
+    }
+
+    #[test]
+    fn test_gamma_rate_threshold() {
+        let report = Report::new(vec![
+            BitNumber::new("110"),
+            BitNumber::new("110"),
+            BitNumber::new("101"),
+        ]);
+
+        // 1 for two thirds of ones or more
+        let majority = Majority {
+            threshold: (2, 3),
+            tie: Bit::One,
+        };
+        let gamma = report.gamma_rate_with(&majority);
+        assert_eq!(gamma.rate, BitNumber::new("110"));
+        assert_eq!(gamma.ties, vec![1]);
+
+        let majority = Majority {
+            threshold: (1, 4),
+            tie: Bit::Zero,
+        };
+        let gamma = report.gamma_rate_with(&majority);
+        assert_eq!(gamma.rate, BitNumber::new("111"));
+        assert!(gamma.ties.is_empty());
+    }
+
+    #[test]
+    fn test_mode() {
+        let rows = vec![
+            BitNumber::new("01"),
+            BitNumber::new("1?"),
+            BitNumber::new("10"),
+        ];
+
+        assert_eq!(
+            Report::validated(rows.clone(), Padding::Reject, Mode::Strict).err(),
+            Some(ReportError::Unknown {
+                line: 2,
+                position: 2
+            })
+        );
+        assert!(Report::validated(rows, Padding::Reject, Mode::Masked).is_ok());
+    }
+
+    #[test]
+    fn test_validated() {
+        let rows = vec![
+            BitNumber::new("101"),
+            BitNumber::new("11"),
+            BitNumber::new("011"),
+        ];
+
+        assert_eq!(
+            Report::validated(rows.clone(), Padding::Reject, Mode::Masked).err(),
+            Some(ReportError::Ragged {
+                line: 2,
+                width: 2,
+                expected: 3
+            })
+        );
+
+        let report = Report::validated(rows, Padding::Left, Mode::Masked).unwrap();
+        assert_eq!(report.size_column(), 3);
+        assert_eq!(report.0[1], BitNumber::new("011"));
+        assert_eq!(report.gamma_rate(), BitNumber::new("011"));
+
+        // the widest row sets the width, even after a short first row
+        let report = Report::validated(
+            vec![BitNumber::new("1"), BitNumber::new("1?0")],
+            Padding::Left,
+            Mode::Masked,
+        )
+        .unwrap();
+        assert_eq!(report.0[0], BitNumber::new("001"));
+    }
+
+    #[test]
+    fn test_validated_empty() {
+        assert_eq!(
+            Report::validated(Vec::new(), Padding::Reject, Mode::Masked).err(),
+            Some(ReportError::Empty)
+        );
+        assert_eq!(
+            Report::validated(Vec::new(), Padding::Left, Mode::Strict).err(),
+            Some(ReportError::Empty)
+        );
+        assert_eq!(Report::new(Vec::new()).size_column(), 0);
+    }
+
+    #[test]
+    fn test_gamma_rate_masked() {
+        // the unknown bit is left out, the last column is a tie of 1 against 1
+        let report = Report::new(vec![
+            BitNumber::new("01"),
+            BitNumber::new("1?"),
+            BitNumber::new("10"),
+        ]);
+        let gamma = report.gamma_rate_with(&Majority::default());
+        assert_eq!(gamma.rate, BitNumber::new("10"));
+        assert_eq!(gamma.ties, vec![1]);
+
+        let report = Report::new(vec![BitNumber::new("?1"), BitNumber::new("?1")]);
+        let gamma = report.gamma_rate_with(&Majority::default());
+        assert_eq!(gamma.ties, vec![0]);
+    }
+
+    #[test]
+    fn test_rate_wildcard() {
+        let report = Report::new(vec![
+            BitNumber::new("0?1"),
+            BitNumber::new("101"),
+            BitNumber::new("100"),
+            BitNumber::new("?10"),
+        ]);
+        // first column: 2 ones against 1 zero, "0?1" is dropped and "?10" kept
+        assert_eq!(report.oxygen_rate(Backend::Filter), BitNumber::new("101"));
+        assert_eq!(report.co2_rate(Backend::Filter), BitNumber::new("0?1"));
+
+        // both rows only differ on unknown bits
+        let report = Report::new(vec![BitNumber::new("1?"), BitNumber::new("?1")]);
+        assert_eq!(report.oxygen_rate(Backend::Filter), BitNumber::new("11"));
+    }
+
     #[test]
     fn test_epsilon_rate() {
 	let report = Report::new(vec![
@@ -246,21 +838,109 @@ mod test_report {
 
     #[test]
     fn test_oxygen_rate() {
-	let report = Report::new(vec![
-	    BitNumber::new("00100"),
-	    BitNumber::new("11110"),
-	    BitNumber::new("10110"),
-	    BitNumber::new("10111"),
-	    BitNumber::new("10101"),
-	    BitNumber::new("01111"),
-	    BitNumber::new("00111"),
-	    BitNumber::new("11100"),
-	    BitNumber::new("10000"),
-	    BitNumber::new("11001"),
-	    BitNumber::new("00010"),
-	    BitNumber::new("01010"),
-	]);
-
-	assert_eq!(report.oxygen_rate(), BitNumber::new("10111"));
+	assert_eq!(example().oxygen_rate(Backend::Filter), BitNumber::new("10111"));
+    }
+
+    fn example() -> Report {
+        Report::new(
+            vec![
+                "00100", "11110", "10110", "10111", "10101", "01111", "00111", "11100", "10000",
+                "11001", "00010", "01010",
+            ]
+            .into_iter()
+            .map(BitNumber::new)
+            .collect(),
+        )
+    }
+
+    #[test]
+    fn test_power_consumption() {
+        assert_eq!(example().power_consumption().to_string(), "198");
+
+        let report = Report::new(vec![
+            BitNumber::new(&format!("1{}", "0".repeat(69))),
+            BitNumber::new(&format!("1{}", "0".repeat(69))),
+            BitNumber::new(&"0".repeat(70)),
+        ]);
+        // 2^69 * (2^69 - 1)
+        assert_eq!(
+            report.power_consumption().to_string(),
+            "348449143727040986585905302199771942879232"
+        );
+    }
+
+    #[test]
+    fn test_co2_rate() {
+        assert_eq!(example().co2_rate(Backend::Filter), BitNumber::new("01010"));
+    }
+
+    #[test]
+    fn test_life_support_rate() {
+        assert_eq!(
+            example()
+                .life_support_rate(Backend::Filter)
+                .unwrap()
+                .to_string(),
+            "230"
+        );
+
+        // the CO2 rating keeps its unknown bit
+        let report = Report::new(vec![
+            BitNumber::new("0?1"),
+            BitNumber::new("101"),
+            BitNumber::new("100"),
+            BitNumber::new("?10"),
+        ]);
+        assert_eq!(
+            report.life_support_rate(Backend::Filter).err(),
+            Some(ConvertError::Unknown { index: 1 })
+        );
+    }
+
+    #[test]
+    fn test_backends() {
+        let report = example();
+        for backend in [Backend::Filter, Backend::Trie] {
+            assert_eq!(report.oxygen_rate(backend), BitNumber::new("10111"));
+            assert_eq!(report.co2_rate(backend), BitNumber::new("01010"));
+        }
+
+        let report = Report::new(format::read("input", Some(Format::Binary)).unwrap());
+        assert_eq!(
+            report.oxygen_rate(Backend::Trie),
+            report.oxygen_rate(Backend::Filter)
+        );
+        assert_eq!(
+            report.co2_rate(Backend::Trie),
+            report.co2_rate(Backend::Filter)
+        );
+
+        // unknown bits fall back to filtering
+        let report = Report::new(vec![BitNumber::new("1?"), BitNumber::new("?1")]);
+        assert_eq!(report.oxygen_rate(Backend::Trie), BitNumber::new("11"));
+    }
+
+    #[test]
+    fn test_rate_missing_bit() {
+        // the CO2 criteria picks a 1 on the second bit when both rows left
+        // have a 0 there
+        let report = Report::new(
+            vec!["000", "001", "110", "111", "100"]
+                .into_iter()
+                .map(BitNumber::new)
+                .collect(),
+        );
+        for backend in [Backend::Filter, Backend::Trie] {
+            assert_eq!(report.oxygen_rate(backend), BitNumber::new("111"));
+            assert_eq!(report.co2_rate(backend), BitNumber::new("000"));
+        }
+    }
+
+    #[test]
+    fn test_rate_ties() {
+        // one row left after the first bit, the second bit is never checked
+        let report = Report::new(vec![BitNumber::new("10"), BitNumber::new("01")]);
+        assert_eq!(report.oxygen_rate(Backend::Filter), BitNumber::new("10"));
+        assert_eq!(report.co2_rate(Backend::Filter), BitNumber::new("01"));
     }
 }
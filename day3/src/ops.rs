@@ -0,0 +1,343 @@
+//! Operators and conversions on `BitNumber`. Unknown bits follow three
+//! valued logic: they stay unknown unless the other operand decides the
+//! result, like `0 & ?`. Both operands must have the same width.
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{BitAnd, BitOr, BitXor, Not, Shl, Shr};
+use std::str::FromStr;
+
+use super::{Bit, BitNumber, WORDS};
+
+impl BitNumber {
+    /// `value` on `width` bits, panics when it doesn't fit.
+    pub fn from_u64(value: u64, width: usize) -> BitNumber {
+        assert!(
+            width >= 64 || value >> width == 0,
+            "{} doesn't fit in {} bits",
+            value,
+            width
+        );
+        let mut words = [0; WORDS];
+        words[0] = value;
+        BitNumber::with_value(words, width)
+    }
+
+    fn check_width(&self, other: &BitNumber) {
+        assert_eq!(
+            self.width, other.width,
+            "can't combine {} and {} bits numbers",
+            self.width, other.width
+        );
+    }
+
+    fn combine(&self, other: &BitNumber, op: fn(u64, u64, u64, u64) -> (u64, u64)) -> BitNumber {
+        self.check_width(other);
+        let mut result = BitNumber::with_value([0; WORDS], self.width);
+        for word in 0..WORDS {
+            let (value, unknown) = op(
+                self.value[word],
+                self.unknown[word],
+                other.value[word],
+                other.unknown[word],
+            );
+            result.value[word] = value & !unknown;
+            result.unknown[word] = unknown;
+        }
+        result
+    }
+}
+
+/// Counts for the callers comparing numbers, the puzzle itself uses none.
+#[allow(dead_code)]
+impl BitNumber {
+    /// Number of bits set to 1.
+    pub fn popcount(&self) -> usize {
+        self.value.iter().map(|val| val.count_ones() as usize).sum()
+    }
+
+    /// Number of positions where the bits differ, an unknown bit only equals
+    /// another unknown bit.
+    pub fn hamming(&self, other: &BitNumber) -> usize {
+        self.check_width(other);
+        (0..WORDS)
+            .map(|word| {
+                ((self.value[word] ^ other.value[word])
+                    | (self.unknown[word] ^ other.unknown[word]))
+                    .count_ones() as usize
+            })
+            .sum()
+    }
+}
+
+impl BitAnd for BitNumber {
+    type Output = BitNumber;
+
+    fn bitand(self, other: BitNumber) -> BitNumber {
+        // unknown unless one side is a known 0
+        self.combine(&other, |left, left_unknown, right, right_unknown| {
+            let value = left & right;
+            let unknown =
+                (left_unknown | right_unknown) & (left | left_unknown) & (right | right_unknown);
+            (value, unknown)
+        })
+    }
+}
+
+impl BitOr for BitNumber {
+    type Output = BitNumber;
+
+    fn bitor(self, other: BitNumber) -> BitNumber {
+        // unknown unless one side is a known 1
+        self.combine(&other, |left, left_unknown, right, right_unknown| {
+            let value = left | right;
+            (value, (left_unknown | right_unknown) & !value)
+        })
+    }
+}
+
+impl BitXor for BitNumber {
+    type Output = BitNumber;
+
+    fn bitxor(self, other: BitNumber) -> BitNumber {
+        self.combine(&other, |left, left_unknown, right, right_unknown| {
+            (left ^ right, left_unknown | right_unknown)
+        })
+    }
+}
+
+impl Not for BitNumber {
+    type Output = BitNumber;
+
+    fn not(self) -> BitNumber {
+        self.invert()
+    }
+}
+
+/// Shift every word of `words` towards the most significant bit.
+fn shift_left(words: &[u64; WORDS], shift: usize) -> [u64; WORDS] {
+    let mut result = [0; WORDS];
+    let (offset, bits) = (shift / 64, shift % 64);
+    for word in offset..WORDS {
+        result[word] = words[word - offset] << bits;
+        if bits > 0 && word > offset {
+            result[word] |= words[word - offset - 1] >> (64 - bits);
+        }
+    }
+    result
+}
+
+fn shift_right(words: &[u64; WORDS], shift: usize) -> [u64; WORDS] {
+    let mut result = [0; WORDS];
+    let (offset, bits) = (shift / 64, shift % 64);
+    for word in 0..WORDS.saturating_sub(offset) {
+        result[word] = words[word + offset] >> bits;
+        if bits > 0 && word + offset + 1 < WORDS {
+            result[word] |= words[word + offset + 1] << (64 - bits);
+        }
+    }
+    result
+}
+
+impl Shl<usize> for BitNumber {
+    type Output = BitNumber;
+
+    /// Towards bit 0, the bits going past it are lost and 0 comes in.
+    fn shl(self, shift: usize) -> BitNumber {
+        if shift >= self.width {
+            return BitNumber::with_value([0; WORDS], self.width);
+        }
+        let mut result = BitNumber {
+            value: shift_left(&self.value, shift),
+            unknown: shift_left(&self.unknown, shift),
+            width: self.width,
+        };
+        for word in 0..WORDS {
+            result.value[word] &= result.full(word);
+            result.unknown[word] &= result.full(word);
+        }
+        result
+    }
+}
+
+impl Shr<usize> for BitNumber {
+    type Output = BitNumber;
+
+    fn shr(self, shift: usize) -> BitNumber {
+        if shift >= self.width {
+            return BitNumber::with_value([0; WORDS], self.width);
+        }
+        BitNumber {
+            value: shift_right(&self.value, shift),
+            unknown: shift_right(&self.unknown, shift),
+            width: self.width,
+        }
+    }
+}
+
+/// A character other than `0`, `1` or `?`, the position starts at 0.
+#[derive(Debug, PartialEq)]
+pub struct ParseBitError {
+    pub position: usize,
+    pub character: char,
+}
+
+impl fmt::Display for ParseBitError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "position {}: '{}' is not a bit",
+            self.position, self.character
+        )
+    }
+}
+
+impl FromStr for BitNumber {
+    type Err = ParseBitError;
+
+    /// Same as `BitNumber::new` but only `?` is read as an unknown bit.
+    fn from_str(raw: &str) -> Result<BitNumber, ParseBitError> {
+        match raw
+            .chars()
+            .enumerate()
+            .find(|(_, val)| !matches!(val, '0' | '1' | '?'))
+        {
+            Some((position, character)) => Err(ParseBitError {
+                position,
+                character,
+            }),
+            None => Ok(BitNumber::new(raw)),
+        }
+    }
+}
+
+impl fmt::Display for BitNumber {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for index in 0..self.width {
+            let current = match self.bit(index) {
+                Bit::One => '1',
+                Bit::Zero => '0',
+                Bit::Unknown => '?',
+            };
+            write!(f, "{}", current)?;
+        }
+        Ok(())
+    }
+}
+
+impl From<u64> for BitNumber {
+    /// On 64 bits, see `BitNumber::from_u64` to pick the width.
+    fn from(value: u64) -> BitNumber {
+        BitNumber::from_u64(value, 64)
+    }
+}
+
+impl Ord for BitNumber {
+    /// By width, then by value, then by unknown bits.
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.width
+            .cmp(&other.width)
+            .then_with(|| self.value.iter().rev().cmp(other.value.iter().rev()))
+            .then_with(|| self.unknown.iter().rev().cmp(other.unknown.iter().rev()))
+    }
+}
+
+impl PartialOrd for BitNumber {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[cfg(test)]
+mod test_ops {
+    use super::*;
+    use std::collections::HashSet;
+
+    fn bits(raw: &str) -> BitNumber {
+        raw.parse().unwrap()
+    }
+
+    #[test]
+    fn test_logic() {
+        assert_eq!(bits("1100") & bits("1010"), bits("1000"));
+        assert_eq!(bits("1100") | bits("1010"), bits("1110"));
+        assert_eq!(bits("1100") ^ bits("1010"), bits("0110"));
+        assert_eq!(!bits("1100"), bits("0011"));
+    }
+
+    #[test]
+    fn test_logic_unknown() {
+        assert_eq!(bits("???") & bits("01?"), bits("0??"));
+        assert_eq!(bits("???") | bits("01?"), bits("?1?"));
+        assert_eq!(bits("?10") ^ bits("01?"), bits("?0?"));
+        assert_eq!(!bits("?10"), bits("?01"));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_width_mismatch() {
+        let _ = bits("10") & bits("100");
+    }
+
+    #[test]
+    fn test_shift() {
+        assert_eq!(bits("10110") << 1, bits("01100"));
+        assert_eq!(bits("10110") >> 2, bits("00101"));
+        assert_eq!(bits("1?110") << 1, bits("?1100"));
+        assert_eq!(bits("10110") << 5, bits("00000"));
+
+        let wide = BitNumber::new(&format!("{}1", "0".repeat(199)));
+        let shifted = wide.clone() << 130;
+        assert!(shifted.is_this_byte_one(199 - 130));
+        assert_eq!(shifted.popcount(), 1);
+        assert_eq!(shifted >> 130, wide);
+    }
+
+    #[test]
+    fn test_parse_display() {
+        assert_eq!(bits("01?1").to_string(), "01?1");
+        assert_eq!(
+            "01x".parse::<BitNumber>(),
+            Err(ParseBitError {
+                position: 2,
+                character: 'x'
+            })
+        );
+    }
+
+    #[test]
+    fn test_from_u64() {
+        assert_eq!(BitNumber::from_u64(5, 4), bits("0101"));
+        assert_eq!(BitNumber::from(5).size(), 64);
+        assert_eq!(BitNumber::from(5).to_u64(), 5);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_from_u64_overflow() {
+        BitNumber::from_u64(16, 4);
+    }
+
+    #[test]
+    fn test_ord_hash() {
+        let mut numbers = vec![bits("110"), bits("011"), bits("10"), bits("111")];
+        numbers.sort();
+        assert_eq!(
+            numbers,
+            vec![bits("10"), bits("011"), bits("110"), bits("111")]
+        );
+
+        let set: HashSet<BitNumber> = vec![bits("01"), bits("01"), bits("0?")]
+            .into_iter()
+            .collect();
+        assert_eq!(set.len(), 2);
+    }
+
+    #[test]
+    fn test_popcount_hamming() {
+        assert_eq!(bits("10110").popcount(), 3);
+        assert_eq!(bits("10110").hamming(&bits("00111")), 2);
+        assert_eq!(bits("1?").hamming(&bits("11")), 1);
+        assert_eq!(bits("1?").hamming(&bits("1?")), 0);
+    }
+}
@@ -0,0 +1,205 @@
+use super::format::{self, Format};
+use super::{report, Report};
+
+/// How close to 50/50 a column has to be to be flagged as unstable.
+const DEFAULT_MARGIN: f64 = 0.05;
+
+#[derive(Debug, PartialEq)]
+pub struct ColumnStats {
+    pub index: usize,
+    pub ones: usize,
+    pub zeros: usize,
+    pub unknowns: usize,
+    /// Share of ones among the known bits, `None` when every bit is unknown.
+    pub ratio: Option<f64>,
+    /// Shannon entropy of the known bits, in bits.
+    pub entropy: f64,
+    /// The gamma bit of this column could flip with a few rows more.
+    pub unstable: bool,
+}
+
+/// Statistics of every column, flagging as unstable the ones with a ratio
+/// within `margin` of 0.5.
+pub fn column_stats(report: &Report, margin: f64) -> Vec<ColumnStats> {
+    let unknowns = report.count_unknowns();
+    report
+        .count_ones()
+        .into_iter()
+        .enumerate()
+        .map(|(index, ones)| {
+            let known = report.size_row() - unknowns[index];
+            let ratio = if known == 0 {
+                None
+            } else {
+                Some(ones as f64 / known as f64)
+            };
+            ColumnStats {
+                index,
+                ones,
+                zeros: known - ones,
+                unknowns: unknowns[index],
+                ratio,
+                entropy: ratio.map(entropy).unwrap_or(0.0),
+                unstable: ratio.map(|val| (val - 0.5).abs() <= margin).unwrap_or(true),
+            }
+        })
+        .collect()
+}
+
+fn entropy(ratio: f64) -> f64 {
+    [ratio, 1.0 - ratio]
+        .iter()
+        .filter(|val| **val > 0.0 && **val < 1.0)
+        .fold(0.0, |total, val| total - val * val.log2())
+}
+
+pub fn table(stats: &[ColumnStats]) -> String {
+    let mut result = String::from("column   ones  zeros unknown  ratio entropy\n");
+    for column in stats {
+        let ratio = match column.ratio {
+            Some(ratio) => format!("{:.3}", ratio),
+            None => String::from("-"),
+        };
+        result.push_str(&format!(
+            "{:>6} {:>6} {:>6} {:>7} {:>6} {:>7.3}{}\n",
+            column.index,
+            column.ones,
+            column.zeros,
+            column.unknowns,
+            ratio,
+            column.entropy,
+            if column.unstable { " unstable" } else { "" }
+        ));
+    }
+    result
+}
+
+pub fn json(stats: &[ColumnStats]) -> String {
+    let columns: Vec<String> = stats
+        .iter()
+        .map(|column| {
+            let ratio = match column.ratio {
+                Some(ratio) => format!("{}", ratio),
+                None => String::from("null"),
+            };
+            format!(
+                "{{\"index\":{},\"ones\":{},\"zeros\":{},\"unknowns\":{},\"ratio\":{},\"entropy\":{},\"unstable\":{}}}",
+                column.index,
+                column.ones,
+                column.zeros,
+                column.unknowns,
+                ratio,
+                column.entropy,
+                column.unstable
+            )
+        })
+        .collect();
+    format!("[{}]", columns.join(","))
+}
+
+/// `stats [--input=<format>] [--json] [--margin=M] [--pad] [--strict] [file]`
+pub fn run(args: &[String]) {
+    let mut filename = "input";
+    let mut input = None;
+    let mut as_json = false;
+    let mut margin = DEFAULT_MARGIN;
+    for arg in args {
+        match arg.split_once('=') {
+            Some(("--input", val)) => input = Some(Format::parse(val)),
+            Some(("--margin", val)) => margin = val.parse().unwrap(),
+            _ if arg == "--json" => as_json = true,
+            _ if arg.starts_with("--") => (),
+            _ => filename = arg,
+        }
+    }
+
+    let report = report(
+        format::read(filename, input).unwrap_or_else(|err| panic!("{}", err)),
+        args,
+    );
+    let stats = column_stats(&report, margin);
+    if as_json {
+        println!("{}", json(&stats));
+    } else {
+        print!("{}", table(&stats));
+    }
+}
+
+#[cfg(test)]
+mod test_stats {
+    use super::super::BitNumber;
+    use super::*;
+
+    fn report() -> Report {
+        Report::new(vec![
+            BitNumber::new("101"),
+            BitNumber::new("11?"),
+            BitNumber::new("000"),
+            BitNumber::new("01?"),
+        ])
+    }
+
+    #[test]
+    fn test_column_stats() {
+        let stats = column_stats(&report(), DEFAULT_MARGIN);
+
+        assert_eq!(stats.len(), 3);
+        assert_eq!(
+            (stats[0].ones, stats[0].zeros, stats[0].unknowns),
+            (2, 2, 0)
+        );
+        assert_eq!(stats[0].ratio, Some(0.5));
+        assert_eq!(stats[0].entropy, 1.0);
+        assert!(stats[0].unstable);
+
+        assert_eq!(
+            (stats[1].ones, stats[1].zeros, stats[1].unknowns),
+            (2, 2, 0)
+        );
+
+        assert_eq!(
+            (stats[2].ones, stats[2].zeros, stats[2].unknowns),
+            (1, 1, 2)
+        );
+
+        let stats = column_stats(
+            &Report::new(vec![
+                BitNumber::new("1"),
+                BitNumber::new("1"),
+                BitNumber::new("1"),
+                BitNumber::new("0"),
+            ]),
+            DEFAULT_MARGIN,
+        );
+        assert_eq!(stats[0].ratio, Some(0.75));
+        assert!((stats[0].entropy - 0.811).abs() < 0.001);
+        assert!(!stats[0].unstable);
+    }
+
+    #[test]
+    fn test_all_unknown() {
+        let report = Report::new(vec![BitNumber::new("?"), BitNumber::new("?")]);
+        let stats = column_stats(&report, DEFAULT_MARGIN);
+
+        assert_eq!(stats[0].ratio, None);
+        assert_eq!(stats[0].entropy, 0.0);
+        assert!(stats[0].unstable);
+        assert_eq!(
+            json(&stats),
+            "[{\"index\":0,\"ones\":0,\"zeros\":0,\"unknowns\":2,\"ratio\":null,\"entropy\":0,\"unstable\":true}]"
+        );
+    }
+
+    #[test]
+    fn test_table() {
+        let report = Report::new(vec![BitNumber::new("10"), BitNumber::new("11")]);
+        let stats = column_stats(&report, DEFAULT_MARGIN);
+
+        assert_eq!(
+            table(&stats),
+            "column   ones  zeros unknown  ratio entropy\n\
+             \x20    0      2      0       0  1.000   0.000\n\
+             \x20    1      1      1       0  0.500   1.000 unstable\n"
+        );
+    }
+}
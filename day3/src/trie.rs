@@ -0,0 +1,116 @@
+use super::{Bit, BitNumber, Report, WORDS};
+
+#[derive(Debug, Clone, Default)]
+struct Node {
+    /// Number of rows going through this node.
+    count: usize,
+    /// Child for a 0 then for a 1.
+    children: [Option<usize>; 2],
+}
+
+/// Prefix trie of the rows of a report, each node knows how many rows share
+/// its prefix. A bit criteria step only reads the two children counts, so a
+/// rating is one walk from the root with no copy of the rows.
+pub struct Trie {
+    nodes: Vec<Node>,
+    width: usize,
+}
+
+impl Trie {
+    /// `None` when a row has an unknown bit: a wildcard row would sit in both
+    /// branches and be counted twice.
+    pub fn new(report: &Report) -> Option<Trie> {
+        let mut trie = Trie {
+            nodes: vec![Node::default()],
+            width: report.size_column(),
+        };
+        for row in &report.0 {
+            row.first_unknown().is_none().then_some(())?;
+            trie.insert(row);
+        }
+        Some(trie)
+    }
+
+    fn insert(&mut self, row: &BitNumber) {
+        let mut current = 0;
+        self.nodes[current].count += 1;
+        for index in 0..self.width {
+            let branch = row.is_this_byte_one(index) as usize;
+            current = match self.nodes[current].children[branch] {
+                Some(child) => child,
+                None => {
+                    self.nodes.push(Node::default());
+                    let child = self.nodes.len() - 1;
+                    self.nodes[current].children[branch] = Some(child);
+                    child
+                }
+            };
+            self.nodes[current].count += 1;
+        }
+    }
+
+    fn count(&self, node: Option<usize>) -> usize {
+        node.map(|val| self.nodes[val].count).unwrap_or(0)
+    }
+
+    /// Same result as `Report::rate`: follow the criteria, or the only
+    /// branch when the criteria picks a missing one.
+    pub fn rate(&self, criteria: fn(usize, usize) -> Bit) -> BitNumber {
+        let mut result = BitNumber::with_value([0; WORDS], self.width);
+        let mut current = 0;
+        for index in 0..self.width {
+            let [zero, one] = self.nodes[current].children;
+            let bit = match criteria(self.count(one), self.count(zero)) {
+                // a criteria picking a missing branch keeps the other one
+                Bit::One if one.is_none() => Bit::Zero,
+                Bit::Zero if zero.is_none() => Bit::One,
+                bit => bit,
+            };
+            result.set(index, bit);
+            current = if bit == Bit::One { one } else { zero }.unwrap();
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod test_trie {
+    use super::super::{least_common, most_common};
+    use super::*;
+
+    #[test]
+    fn test_rate() {
+        let report = Report::new(
+            vec![
+                "00100", "11110", "10110", "10111", "10101", "01111", "00111", "11100", "10000",
+                "11001", "00010", "01010",
+            ]
+            .into_iter()
+            .map(BitNumber::new)
+            .collect(),
+        );
+        let trie = Trie::new(&report).unwrap();
+
+        assert_eq!(trie.rate(most_common), BitNumber::new("10111"));
+        assert_eq!(trie.rate(least_common), BitNumber::new("01010"));
+    }
+
+    #[test]
+    fn test_duplicates() {
+        let report = Report::new(vec![
+            BitNumber::new("110"),
+            BitNumber::new("110"),
+            BitNumber::new("011"),
+        ]);
+        let trie = Trie::new(&report).unwrap();
+
+        assert_eq!(trie.rate(most_common), BitNumber::new("110"));
+        assert_eq!(trie.rate(least_common), BitNumber::new("011"));
+    }
+
+    #[test]
+    fn test_unknown() {
+        let report = Report::new(vec![BitNumber::new("1?"), BitNumber::new("01")]);
+        assert!(Trie::new(&report).is_none());
+    }
+}
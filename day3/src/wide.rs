@@ -0,0 +1,106 @@
+use std::fmt;
+use std::ops::Mul;
+
+/// Unsigned integer of any size, the `u64` limbs are little endian and the
+/// last one is never 0.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Wide(Vec<u64>);
+
+impl Wide {
+    pub fn new(limbs: &[u64]) -> Wide {
+        let mut result = Wide(limbs.to_vec());
+        result.trim();
+        result
+    }
+
+    fn trim(&mut self) {
+        while self.0.last() == Some(&0) {
+            self.0.pop();
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Divide in place and return the remainder.
+    fn div_rem(&mut self, divisor: u64) -> u64 {
+        let mut remainder: u128 = 0;
+        for limb in self.0.iter_mut().rev() {
+            let current = (remainder << 64) | *limb as u128;
+            *limb = (current / divisor as u128) as u64;
+            remainder = current % divisor as u128;
+        }
+        self.trim();
+        remainder as u64
+    }
+}
+
+impl Mul for Wide {
+    type Output = Wide;
+
+    fn mul(self, other: Wide) -> Wide {
+        let mut result = vec![0u64; self.0.len() + other.0.len()];
+        for (i, left) in self.0.iter().enumerate() {
+            let mut carry: u128 = 0;
+            for (j, right) in other.0.iter().enumerate() {
+                let current = result[i + j] as u128 + *left as u128 * *right as u128 + carry;
+                result[i + j] = current as u64;
+                carry = current >> 64;
+            }
+            result[i + other.0.len()] = carry as u64;
+        }
+        Wide::new(&result)
+    }
+}
+
+impl fmt::Display for Wide {
+    /// Decimal, built from chunks of 19 digits.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        const CHUNK: u64 = 10_000_000_000_000_000_000;
+        if self.is_zero() {
+            return write!(f, "0");
+        }
+        let mut current = self.clone();
+        let mut chunks = Vec::new();
+        while !current.is_zero() {
+            chunks.push(current.div_rem(CHUNK));
+        }
+        write!(f, "{}", chunks.pop().unwrap())?;
+        for chunk in chunks.iter().rev() {
+            write!(f, "{:019}", chunk)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test_wide {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        assert_eq!(Wide::new(&[]).to_string(), "0");
+        assert_eq!(Wide::new(&[0, 0]).to_string(), "0");
+        assert_eq!(Wide::new(&[230]).to_string(), "230");
+        assert_eq!(Wide::new(&[u64::MAX]).to_string(), u64::MAX.to_string());
+        assert_eq!(Wide::new(&[0, 1]).to_string(), (1u128 << 64).to_string());
+    }
+
+    #[test]
+    fn test_mul() {
+        let result = Wide::new(&[u64::MAX]) * Wide::new(&[u64::MAX]);
+        assert_eq!(
+            result.to_string(),
+            (u64::MAX as u128 * u64::MAX as u128).to_string()
+        );
+
+        // (2^128 - 1)^2
+        let max = Wide::new(&[u64::MAX, u64::MAX]);
+        assert_eq!(
+            (max.clone() * max).to_string(),
+            "115792089237316195423570985008687907852589419931798687112530834793049593217025"
+        );
+        assert!((Wide::new(&[5]) * Wide::new(&[])).is_zero());
+    }
+}
@@ -0,0 +1,248 @@
+use std::collections::HashMap;
+
+use super::pattern::{Compiled, Pattern};
+use super::Grid;
+
+/// A board completing a pattern.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Win {
+    /// Index of the board in the input.
+    pub board: usize,
+    /// Number of draws up to the winning one.
+    pub turn: usize,
+    pub number: usize,
+    pub unmarked_sum: u32,
+    pub score: u32,
+    pub pattern: Pattern,
+}
+
+pub struct Game {
+    pub draws: Vec<usize>,
+    pub grids: Vec<Grid>,
+    pub patterns: Vec<Pattern>,
+    /// Number of draws done.
+    pub turn: usize,
+    pub wins: Vec<Win>,
+    /// Every `(board, cell)` holding a number, in board order.
+    index: HashMap<usize, Vec<(usize, usize)>>,
+    compiled: HashMap<(usize, usize), Compiled>,
+    playing: Vec<bool>,
+}
+
+impl Game {
+    pub fn new(draws: Vec<usize>, grids: Vec<Grid>, patterns: Vec<Pattern>) -> Game {
+        Game::resume(draws, grids, patterns, 0, Vec::new())
+    }
+
+    /// Game after `turn` draws, the grids already marked and `wins` the wins
+    /// so far.
+    pub fn resume(
+        draws: Vec<usize>,
+        grids: Vec<Grid>,
+        patterns: Vec<Pattern>,
+        turn: usize,
+        wins: Vec<Win>,
+    ) -> Game {
+        let mut index: HashMap<usize, Vec<(usize, usize)>> = HashMap::new();
+        let mut compiled = HashMap::new();
+        for (board, grid) in grids.iter().enumerate() {
+            for (cell, number) in grid.numbers.iter().enumerate() {
+                index.entry(*number).or_default().push((board, cell));
+            }
+            compiled
+                .entry((grid.width, grid.height))
+                .or_insert_with(|| Compiled::new(&patterns, grid.width, grid.height));
+        }
+        let mut playing = vec![true; grids.len()];
+        for win in &wins {
+            playing[win.board] = false;
+        }
+        Game {
+            draws,
+            grids,
+            patterns,
+            turn,
+            wins,
+            index,
+            compiled,
+            playing,
+        }
+    }
+
+    /// The game is over once every board won or every number is drawn.
+    pub fn is_over(&self) -> bool {
+        self.turn == self.draws.len() || !self.playing.contains(&true)
+    }
+
+    /// Draw the next number and return the boards it makes win, in input
+    /// order. A board stops playing once it won.
+    pub fn step(&mut self) -> &[Win] {
+        if self.is_over() {
+            return &[];
+        }
+        let number = self.draws[self.turn];
+        self.turn += 1;
+        let before = self.wins.len();
+        let positions = self.index.get(&number).map(Vec::as_slice).unwrap_or(&[]);
+        for (position, (board, cell)) in positions.iter().enumerate() {
+            if !self.playing[*board] {
+                continue;
+            }
+            let grid = &mut self.grids[*board];
+            grid.mark(*cell);
+            // a number found twice on a board is checked once both are marked
+            if positions.get(position + 1).map(|next| next.0) == Some(*board) {
+                continue;
+            }
+            let found = self.compiled[&(grid.width, grid.height)].find(&grid.marks);
+            if let Some(pattern) = found {
+                let unmarked_sum = grid.unmarked_sum();
+                self.wins.push(Win {
+                    board: *board,
+                    turn: self.turn,
+                    number,
+                    unmarked_sum,
+                    score: number as u32 * unmarked_sum,
+                    pattern: self.patterns[pattern].clone(),
+                });
+                self.playing[*board] = false;
+            }
+        }
+        &self.wins[before..]
+    }
+
+    /// Draw every number left and return all the wins in order, with the
+    /// ones before a resume.
+    pub fn play(mut self) -> Vec<Win> {
+        while !self.is_over() {
+            self.step();
+        }
+        self.wins
+    }
+}
+
+#[cfg(test)]
+pub mod test_game {
+    use super::super::{parse, pattern, Input};
+    use super::*;
+
+    /// The puzzle example.
+    pub const EXAMPLE: &str =
+        "7,4,9,5,11,17,23,2,0,14,21,24,10,16,13,6,15,25,12,22,18,20,8,19,3,26,1
+
+22 13 17 11  0
+ 8  2 23  4 24
+21  9 14 16  7
+ 6 10  3 18  5
+ 1 12 20 15 19
+
+ 3 15  0  2 22
+ 9 18 13 17  5
+19  8  7 25 23
+20 11 10 24  4
+14 21 16 12  6
+
+14 21 17 24  4
+10 16 15  9 19
+18  8 23 26 20
+22 11 13  6  5
+ 2  0 12  3  7
+";
+
+    fn game(patterns: Vec<Pattern>) -> Game {
+        let Input { draws, grids, .. } = parse(EXAMPLE, None).unwrap();
+        Game::new(draws, grids, patterns)
+    }
+
+    #[test]
+    fn test_play() {
+        let wins = game(pattern::STANDARD.to_vec()).play();
+
+        assert_eq!(wins.len(), 3);
+        assert_eq!(
+            wins[0],
+            Win {
+                board: 2,
+                turn: 12,
+                number: 24,
+                unmarked_sum: 188,
+                score: 4512,
+                pattern: Pattern::Line,
+            }
+        );
+        assert_eq!(wins[2].board, 1);
+        assert_eq!(wins[2].number, 13);
+        assert_eq!(wins[2].unmarked_sum, 148);
+        assert_eq!(wins[2].score, 1924);
+        assert!(wins.windows(2).all(|pair| pair[0].turn <= pair[1].turn));
+    }
+
+    #[test]
+    fn test_no_winner() {
+        let wins = Game::new(
+            vec![1, 2],
+            parse(EXAMPLE, None).unwrap().grids,
+            vec![Pattern::Blackout],
+        )
+        .play();
+        assert!(wins.is_empty());
+    }
+
+    /// The scan of every cell by `Grid::check_number`.
+    fn play_scanning(game: Game) -> Vec<Win> {
+        let mut grids = game.grids;
+        let mut wins = Vec::new();
+        for (turn, number) in game.draws.iter().enumerate() {
+            for (board, grid) in grids.iter_mut().enumerate() {
+                if wins.iter().any(|win: &Win| win.board == board) {
+                    continue;
+                }
+                grid.check_number(*number);
+                if let Some(pattern) = grid.winning_pattern(&game.patterns) {
+                    wins.push(Win {
+                        board,
+                        turn: turn + 1,
+                        number: *number,
+                        unmarked_sum: grid.unmarked_sum(),
+                        score: *number as u32 * grid.unmarked_sum(),
+                        pattern: pattern.clone(),
+                    });
+                }
+            }
+        }
+        wins
+    }
+
+    #[test]
+    fn test_same_as_scanning() {
+        for patterns in [
+            pattern::STANDARD.to_vec(),
+            vec![Pattern::Corners, Pattern::X],
+            vec![Pattern::Blackout],
+        ] {
+            assert_eq!(game(patterns.clone()).play(), play_scanning(game(patterns)));
+        }
+
+        // duplicated numbers on a board
+        let content = "5,1,2,3\n\n1 2\n5 5\n\n5 1\n3 2\n";
+        let Input { draws, grids, .. } = parse(content, None).unwrap();
+        let make = || Game::new(draws.clone(), grids.clone(), pattern::STANDARD.to_vec());
+        assert_eq!(make().play(), play_scanning(make()));
+        assert_eq!(make().play()[0].turn, 1);
+    }
+
+    #[test]
+    fn test_same_turn() {
+        let grids = parse("0\n\n1 2\n3 4\n\n4 2\n1 3\n", None).unwrap().grids;
+        let wins = Game::new(vec![1, 3, 2], grids, pattern::STANDARD.to_vec()).play();
+
+        assert_eq!(
+            wins.iter()
+                .map(|win| (win.board, win.turn, win.score))
+                .collect::<Vec<_>>(),
+            vec![(0, 2, 18), (1, 2, 18)]
+        );
+        assert_eq!(wins[0].pattern, Pattern::Column);
+        assert_eq!(wins[1].pattern, Pattern::Line);
+    }
+}
@@ -1,5 +1,22 @@
+use std::collections::HashSet;
+use std::env;
+use std::fmt;
 use std::fs;
 
+mod game;
+mod marks;
+mod montecarlo;
+mod pattern;
+mod predict;
+mod render;
+mod server;
+mod snapshot;
+
+use game::Game;
+use marks::Marks;
+use pattern::{Mask, Pattern};
+use predict::Predictor;
+
 #[derive(Debug, PartialEq, Clone, Copy)]
 struct MarkedNumber {
     value: usize,
@@ -20,10 +37,6 @@ impl MarkedNumber {
     pub fn is_marked(&self) -> bool {
         self.marked
     }
-
-    pub fn mark(&mut self) {
-        self.marked = true;
-    }
 }
 #[cfg(test)]
 mod test_marked_number {
@@ -31,73 +44,116 @@ mod test_marked_number {
 
     #[test]
     fn test_marked_number() {
-        let mut number = MarkedNumber::new("25");
+        let number = MarkedNumber::new("25");
         assert_eq!(number.value(), 25);
         assert!(!number.is_marked());
 
-        number.mark();
-        assert!(number.is_marked());
+        let mut grid = Grid::new(vec!["25"]);
+        grid.mark(0);
+        assert!(grid.cell(0).is_marked());
     }
 }
 
+/// Board of `width` columns and `height` lines, the numbers are stored line
+/// by line.
 #[derive(Debug, PartialEq, Clone)]
 struct Grid {
-    numbers: Vec<MarkedNumber>,
+    numbers: Vec<usize>,
+    marks: Marks,
+    width: usize,
+    height: usize,
 }
 
 impl Grid {
+    /// Square grid, the side is the square root of the number of values. The
+    /// input gives the size, this is for the tests.
+    #[cfg(test)]
     pub fn new(values: Vec<&str>) -> Grid {
+        let side = (1..=values.len())
+            .find(|side| side * side >= values.len())
+            .unwrap_or(0);
+        Grid::with_size(values, side, side)
+    }
+
+    pub fn with_size(values: Vec<&str>, width: usize, height: usize) -> Grid {
+        assert_eq!(
+            values.len(),
+            width * height,
+            "{} values for a {}x{} grid",
+            values.len(),
+            width,
+            height
+        );
         Grid {
-            numbers: values.iter().map(|val| MarkedNumber::new(val)).collect(),
+            numbers: values
+                .iter()
+                .map(|val| MarkedNumber::new(val).value())
+                .collect(),
+            marks: Marks::new(width * height),
+            width,
+            height,
+        }
+    }
+
+    /// Number and mark of the cell at `index`, line by line.
+    pub fn cell(&self, index: usize) -> MarkedNumber {
+        MarkedNumber {
+            value: self.numbers[index],
+            marked: self.marks.get(index),
         }
     }
 
+    pub fn mark(&mut self, index: usize) {
+        self.marks.set(index);
+    }
+
+    /// Scan every cell, see `Game` for an index of the numbers.
     pub fn check_number(&mut self, val: usize) {
-        for mut current in self.numbers.iter_mut() {
-            if current.value() == val {
-                current.mark();
+        for index in 0..self.numbers.len() {
+            if self.numbers[index] == val {
+                self.mark(index);
             }
         }
     }
 
+    /// A full line or column.
     pub fn is_bingo(&self) -> bool {
-        for index in 0..5 {
-            if self.check_line(index) {
-                return true;
-            }
-        }
+        (0..self.height).any(|index| self.check_line(index))
+            || (0..self.width).any(|index| self.check_col(index))
+    }
 
-        for index in 0..5 {
-            if self.check_col(index) {
-                return true;
-            }
-        }
-        false
+    /// Cells of the first of `patterns` with all its cells marked.
+    pub fn winning_cells(&self, patterns: &[Pattern]) -> Option<Vec<usize>> {
+        patterns.iter().find_map(|pattern| {
+            pattern
+                .cells(self.width, self.height)
+                .into_iter()
+                .find(|cells| cells.iter().all(|index| self.marks.get(*index)))
+        })
+    }
+
+    /// First of `patterns` with all its cells marked, `Game` keeps the
+    /// compiled patterns instead.
+    #[cfg(test)]
+    pub fn winning_pattern<'a>(&self, patterns: &'a [Pattern]) -> Option<&'a Pattern> {
+        pattern::Compiled::new(patterns, self.width, self.height)
+            .find(&self.marks)
+            .map(|index| &patterns[index])
     }
 
     fn check_line(&self, index: usize) -> bool {
-        self.numbers[index * 5..(index * 5) + 5]
-            .iter()
-            .filter(|val| val.is_marked())
-            .collect::<Vec<&MarkedNumber>>()
-            .len()
-            == 5
+        (index * self.width..(index + 1) * self.width).all(|cell| self.marks.get(cell))
     }
 
     fn check_col(&self, index: usize) -> bool {
-        self.numbers
-            .iter()
-            .skip(index)
-            .step_by(5)
-            .filter(|val| val.is_marked())
-            .collect::<Vec<&MarkedNumber>>()
-            .len()
-            == 5
+        (index..self.numbers.len())
+            .step_by(self.width)
+            .all(|cell| self.marks.get(cell))
     }
 
     pub fn unmarked_sum(&self) -> u32 {
-        self.numbers
-            .iter()
+        (0..self.numbers.len())
+            .map(|index| self.cell(index))
             .filter(|val| !val.is_marked())
             .map(|val| val.value() as u32)
             .sum()
@@ -114,6 +170,47 @@ mod test_grid {
             "1", "2", "3", "4", "5", "6", "7", "8", "9", "10", "11", "12", "13", "14", "15", "16",
             "17", "18", "19", "20", "21", "22", "23", "24", "25",
         ]);
+        assert_eq!((grid.width, grid.height), (5, 5));
+        assert_eq!(grid.unmarked_sum(), 325);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_with_size_mismatch() {
+        Grid::with_size(vec!["1", "2", "3"], 2, 2);
+    }
+
+    #[test]
+    fn test_small_grid() {
+        let mut grid = Grid::new(vec!["1", "2", "3", "4", "5", "6", "7", "8", "9"]);
+        assert_eq!((grid.width, grid.height), (3, 3));
+
+        for number in [2, 5] {
+            grid.check_number(number);
+        }
+        assert!(!grid.is_bingo());
+        grid.check_number(8);
+        assert!(grid.check_col(1));
+        assert!(grid.is_bingo());
+        assert_eq!(grid.unmarked_sum(), 30);
+    }
+
+    #[test]
+    fn test_non_square() {
+        // 4 columns and 2 lines
+        let mut grid = Grid::with_size(vec!["1", "2", "3", "4", "5", "6", "7", "8"], 4, 2);
+
+        grid.check_number(3);
+        grid.check_number(7);
+        assert!(grid.check_col(2));
+        assert!(grid.is_bingo());
+
+        let mut grid = Grid::with_size(vec!["1", "2", "3", "4", "5", "6", "7", "8"], 4, 2);
+        for number in 5..9 {
+            grid.check_number(number);
+        }
+        assert!(grid.check_line(1));
+        assert!(!grid.check_line(0));
     }
 
     #[test]
@@ -159,65 +256,476 @@ mod test_grid {
         assert!(grid.is_bingo());
         assert_eq!(grid.unmarked_sum(), 260);
     }
+
+    #[test]
+    fn test_winning_pattern() {
+        let mut grid = Grid::new(vec!["1", "2", "3", "4", "5", "6", "7", "8", "9"]);
+        let patterns = [Pattern::Corners, Pattern::X, Pattern::Blackout];
+
+        for number in [1, 5, 9, 3] {
+            grid.check_number(number);
+        }
+        assert_eq!(grid.winning_pattern(&patterns), None);
+        assert_eq!(
+            grid.winning_pattern(&[Pattern::Diagonal, Pattern::Line]),
+            Some(&Pattern::Diagonal)
+        );
+
+        grid.check_number(7);
+        assert_eq!(grid.winning_pattern(&patterns), Some(&Pattern::Corners));
+        assert_eq!(grid.winning_pattern(&patterns[1..]), Some(&Pattern::X));
+        assert!(!grid.is_bingo());
+
+        for number in [2, 4, 6, 8] {
+            grid.check_number(number);
+        }
+        assert_eq!(
+            grid.winning_pattern(&[Pattern::Blackout]),
+            Some(&Pattern::Blackout)
+        );
+    }
+
+    #[test]
+    fn test_winning_mask() {
+        let plus = Pattern::Mask(Mask::parse("plus", ".#.\n###\n.#.").unwrap());
+        let values: Vec<String> = (1..17).map(|val| val.to_string()).collect();
+        let mut grid = Grid::new(values.iter().map(|val| val.as_str()).collect());
+
+        // centered on 11, in the bottom right part of the 4x4 grid
+        for number in [7, 10, 11, 12, 15] {
+            grid.check_number(number);
+        }
+        assert_eq!(grid.winning_pattern(&pattern::STANDARD), None);
+        assert_eq!(
+            grid.winning_pattern(&[plus]).map(|val| val.to_string()),
+            Some(String::from("plus"))
+        );
+    }
 }
 
-fn parse(filename: &str) -> (Vec<usize>, Vec<Grid>) {
-    let content = fs::read_to_string(filename).expect("can't read input");
-    let lines = content
+/// Board size as `(width, height)`.
+type Size = (usize, usize);
+
+/// What is wrong with the input at `line`, starting at 1.
+#[derive(Debug, PartialEq)]
+struct Problem {
+    line: usize,
+    kind: ProblemKind,
+}
+
+#[derive(Debug, PartialEq)]
+enum ProblemKind {
+    NoDraws,
+    NoBoards,
+    NotANumber(String),
+    /// A row with another width than the first row of its board.
+    RaggedRow {
+        width: usize,
+        expected: usize,
+    },
+    /// A board with another number of values than the declared size.
+    WrongSize {
+        count: usize,
+        expected: usize,
+    },
+    /// A number already on the same board.
+    Duplicate(usize),
+    /// A draw found on no board.
+    UnusedDraw(usize),
+}
+
+impl Problem {
+    /// The game can still be played.
+    fn is_warning(&self) -> bool {
+        matches!(
+            self.kind,
+            ProblemKind::Duplicate(_) | ProblemKind::UnusedDraw(_)
+        )
+    }
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+        match &self.kind {
+            ProblemKind::NoDraws => write!(f, "no draws"),
+            ProblemKind::NoBoards => write!(f, "no boards"),
+            ProblemKind::NotANumber(token) => write!(f, "'{}' is not a number", token),
+            ProblemKind::RaggedRow { width, expected } => {
+                write!(f, "{} numbers in the row instead of {}", width, expected)
+            }
+            ProblemKind::WrongSize { count, expected } => {
+                write!(f, "{} numbers in the board instead of {}", count, expected)
+            }
+            ProblemKind::Duplicate(number) => write!(f, "{} is already on the board", number),
+            ProblemKind::UnusedDraw(number) => write!(f, "{} is drawn but on no board", number),
+        }
+    }
+}
+
+/// Draws and grids, with the problems that don't stop a game.
+#[derive(Debug)]
+struct Input {
+    draws: Vec<usize>,
+    grids: Vec<Grid>,
+    warnings: Vec<Problem>,
+}
+
+/// Draws on the first line then grids separated by blank lines. With no
+/// `size` each line is a row and all the rows of a board must have the same
+/// width, else a board must hold `size` numbers whatever the layout. All the
+/// problems are returned, warnings included, when one is not a warning.
+fn parse(content: &str, size: Option<Size>) -> Result<Input, Vec<Problem>> {
+    let mut problems = Vec::new();
+
+    let mut lines = content
         .lines()
-        .filter(|current| !current.is_empty())
-        .map(|val| val.clone())
-        .collect::<Vec<&str>>();
+        .enumerate()
+        .map(|(index, line)| (index + 1, line));
+    let draws: Vec<usize> = match lines.next() {
+        Some((line, raw)) if !raw.trim().is_empty() => raw
+            .split(',')
+            .filter_map(|token| number(line, token.trim(), &mut problems))
+            .collect(),
+        _ => {
+            return Err(vec![Problem {
+                line: 1,
+                kind: ProblemKind::NoDraws,
+            }])
+        }
+    };
 
-    let numbers: Vec<usize> = lines[0]
-        .split(',')
-        .map(|val| val.parse::<usize>().unwrap())
-        .collect::<Vec<usize>>();
+    let mut blocks: Vec<Vec<(usize, Vec<&str>)>> = vec![Vec::new()];
+    for (line, raw) in lines {
+        let row: Vec<&str> = raw.split_whitespace().collect();
+        if !row.is_empty() {
+            blocks.last_mut().unwrap().push((line, row));
+        } else if !blocks.last().unwrap().is_empty() {
+            blocks.push(Vec::new());
+        }
+    }
+    blocks.retain(|block| !block.is_empty());
 
-    let mut tmp: Vec<&str> = Vec::new();
-    let mut grids: Vec<Grid> = Vec::new();
+    let mut grids = Vec::new();
+    // every number on a board, the rejected ones too
+    let mut used = HashSet::new();
+    for block in &blocks {
+        let mut valid = true;
+        let mut seen = HashSet::new();
+        for (line, row) in block {
+            for token in row {
+                match number(*line, token, &mut problems) {
+                    Some(value) => {
+                        used.insert(value);
+                        if !seen.insert(value) {
+                            problems.push(Problem {
+                                line: *line,
+                                kind: ProblemKind::Duplicate(value),
+                            });
+                        }
+                    }
+                    None => valid = false,
+                }
+            }
+        }
+
+        let values: Vec<&str> = block.iter().flat_map(|(_, row)| row.clone()).collect();
+        let (width, height) = match size {
+            Some((width, height)) => {
+                if values.len() != width * height {
+                    problems.push(Problem {
+                        line: block[0].0,
+                        kind: ProblemKind::WrongSize {
+                            count: values.len(),
+                            expected: width * height,
+                        },
+                    });
+                    valid = false;
+                }
+                (width, height)
+            }
+            None => {
+                let expected = block[0].1.len();
+                for (line, row) in block.iter().filter(|(_, row)| row.len() != expected) {
+                    problems.push(Problem {
+                        line: *line,
+                        kind: ProblemKind::RaggedRow {
+                            width: row.len(),
+                            expected,
+                        },
+                    });
+                    valid = false;
+                }
+                (expected, block.len())
+            }
+        };
+        if valid {
+            grids.push(Grid::with_size(values, width, height));
+        }
+    }
+    if blocks.is_empty() {
+        problems.push(Problem {
+            line: content.lines().count() + 1,
+            kind: ProblemKind::NoBoards,
+        });
+    }
 
-    for line in lines.iter().skip(1) {
-        let mut foo: Vec<&str> = line
-            .split(' ')
-            .filter(|val| val != &"")
-            .collect::<Vec<&str>>();
-        tmp.append(&mut foo);
-        if tmp.len() < 25 {
-            continue;
+    for draw in &draws {
+        if !used.contains(draw) {
+            problems.push(Problem {
+                line: 1,
+                kind: ProblemKind::UnusedDraw(*draw),
+            });
         }
-        let grid = Grid::new(tmp.clone());
-        grids.push(grid);
-        tmp = Vec::new();
     }
-    return (numbers, grids);
+    // stable, the problems of a line stay in reading order
+    problems.sort_by_key(|problem| problem.line);
+    if !problems.iter().all(|problem| problem.is_warning()) {
+        return Err(problems);
+    }
+    Ok(Input {
+        draws,
+        grids,
+        warnings: problems,
+    })
 }
 
-fn main() {
-    let (numbers, mut grids) = parse("input");
+fn number(line: usize, token: &str, problems: &mut Vec<Problem>) -> Option<usize> {
+    match token.parse::<usize>() {
+        Ok(value) => Some(value),
+        Err(_) => {
+            problems.push(Problem {
+                line,
+                kind: ProblemKind::NotANumber(String::from(token)),
+            });
+            None
+        }
+    }
+}
 
-    'outer: for number in numbers {
-        for grid in grids.iter_mut() {
-            grid.check_number(number);
-            if grid.is_bingo() {
-                println!("part1 {}", number as u32 * grid.unmarked_sum());
-                break 'outer;
+/// `WxH` as in `5x5`.
+fn parse_size(raw: &str) -> Size {
+    let (width, height) = raw.split_once('x').expect("size is WxH");
+    (width.parse().unwrap(), height.parse().unwrap())
+}
+
+/// `--pattern=<name>,...` picks built in patterns and each `--mask=<file>`
+/// adds a mask, rows and columns by default.
+fn patterns(args: &[String]) -> Vec<Pattern> {
+    let mut result = Vec::new();
+    for arg in args {
+        match arg.split_once('=') {
+            Some(("--pattern", names)) => result.extend(names.split(',').map(|name| {
+                Pattern::parse(name).unwrap_or_else(|| panic!("unknown pattern {}", name))
+            })),
+            Some(("--mask", filename)) => {
+                let text = fs::read_to_string(filename).expect("can't read mask");
+                match Mask::parse(filename, &text) {
+                    Ok(mask) => result.push(Pattern::Mask(mask)),
+                    Err(err) => panic!("{}: {}", filename, err),
+                }
             }
+            _ => (),
         }
     }
+    if result.is_empty() {
+        result.extend(pattern::STANDARD);
+    }
+    result
+}
 
-    let mut score: u32 = 0;
-    let (numbers, mut grids) = parse("input");
-    for number in numbers {
-        for grid in grids.iter_mut() {
-            if grid.is_bingo() {
-                continue;
+/// Parse `filename` with the `--size=WxH` of `args`, the problems are
+/// printed on stderr and panic when one is not a warning.
+fn read_input(filename: &str, args: &[String]) -> Input {
+    let size = args
+        .iter()
+        .find_map(|val| val.strip_prefix("--size=").map(parse_size));
+    let content = fs::read_to_string(filename).expect("can't read input");
+    match parse(&content, size) {
+        Ok(input) => {
+            for warning in &input.warnings {
+                eprintln!("warning: {}", warning);
             }
-            grid.check_number(number);
-            if grid.is_bingo() {
-                score = number as u32 * grid.unmarked_sum();
+            input
+        }
+        Err(problems) => {
+            for problem in &problems {
+                eprintln!("{}", problem);
             }
+            panic!("{} problems in input", problems.len());
+        }
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    match args.get(1).map(|val| val.as_str()) {
+        Some("replay") => return render::run(&args[2..]),
+        Some("serve") => return server::run(&args[2..]),
+        Some("montecarlo") => return montecarlo::run(&args[2..]),
+        Some("save") => return snapshot::run_save(&args[2..]),
+        Some("resume") => return snapshot::run_resume(&args[2..]),
+        _ => (),
+    }
+
+    let patterns = patterns(&args);
+    let Input { draws, grids, .. } = read_input("input", &args);
+
+    let wins = if args.iter().any(|val| val == "--predict") {
+        Predictor::new(draws, patterns).predict_all(&grids)
+    } else {
+        Game::new(draws, grids, patterns).play()
+    };
+    if let Some(first) = wins.first() {
+        println!("part1 {} ({})", first.score, first.pattern);
+    }
+    if let Some(last) = wins.last() {
+        println!("part2 {} ({})", last.score, last.pattern);
+    }
+    if let Some(turn) = args.iter().find_map(|val| val.strip_prefix("--at=")) {
+        for win in predict::winners_at(&wins, turn.parse().unwrap()) {
+            println!(
+                "turn {}: board {} scores {}",
+                win.turn, win.board, win.score
+            );
         }
     }
-    println!("part2 {}", score);
+}
+
+#[cfg(test)]
+mod test_parse {
+    use super::*;
+
+    #[test]
+    fn test_layout() {
+        let content =
+            "4,2,9\n\n 1  2  3\n 4  5  6\n 7  8  9\n\n1 2 3 4 5 6 7\n8 9 10 11 12 13 14\n";
+        let Input { draws, grids, .. } = parse(content, None).unwrap();
+
+        assert_eq!(draws, vec![4, 2, 9]);
+        assert_eq!(grids.len(), 2);
+        assert_eq!((grids[0].width, grids[0].height), (3, 3));
+        assert_eq!((grids[1].width, grids[1].height), (7, 2));
+        assert_eq!(grids[1].unmarked_sum(), 105);
+    }
+
+    #[test]
+    fn test_declared_size() {
+        // the layout is ignored
+        let content = "1\n1 2 3 4\n5 6\n\n7 8 9 10 11 12\n";
+        let grids = parse(content, Some((3, 2))).unwrap().grids;
+
+        assert_eq!(grids.len(), 2);
+        assert_eq!(
+            grids[1],
+            Grid::with_size(vec!["7", "8", "9", "10", "11", "12"], 3, 2)
+        );
+    }
+
+    #[test]
+    fn test_problems() {
+        // a missing number, a bad token, a bad draw and one on no board, the
+        // draws on the rejected boards are used
+        let content = "1,x,3,11\n\n1 2\n3\n\n4 5\n6 y\n\n7 8\n9 10\n";
+        let problems = parse(content, None).unwrap_err();
+
+        assert_eq!(
+            problems,
+            vec![
+                Problem {
+                    line: 1,
+                    kind: ProblemKind::NotANumber(String::from("x"))
+                },
+                Problem {
+                    line: 1,
+                    kind: ProblemKind::UnusedDraw(11)
+                },
+                Problem {
+                    line: 4,
+                    kind: ProblemKind::RaggedRow {
+                        width: 1,
+                        expected: 2
+                    }
+                },
+                Problem {
+                    line: 7,
+                    kind: ProblemKind::NotANumber(String::from("y"))
+                },
+            ]
+        );
+        assert_eq!(
+            problems[2].to_string(),
+            "line 4: 1 numbers in the row instead of 2"
+        );
+    }
+
+    #[test]
+    fn test_warnings() {
+        let content = "1,2,9\n\n1 2\n2 3\n";
+        let input = parse(content, None).unwrap();
+
+        assert_eq!(input.grids.len(), 1);
+        assert_eq!(
+            input.warnings,
+            vec![
+                Problem {
+                    line: 1,
+                    kind: ProblemKind::UnusedDraw(9)
+                },
+                Problem {
+                    line: 4,
+                    kind: ProblemKind::Duplicate(2)
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_wrong_size() {
+        let content = "1\n\n1 2 3\n4 5 6\n\n1 2 3 4 5\n";
+        assert_eq!(
+            parse(content, Some((3, 2))).unwrap_err(),
+            vec![Problem {
+                line: 6,
+                kind: ProblemKind::WrongSize {
+                    count: 5,
+                    expected: 6
+                }
+            }]
+        );
+    }
+
+    #[test]
+    fn test_empty() {
+        assert_eq!(
+            parse("", None).unwrap_err(),
+            vec![Problem {
+                line: 1,
+                kind: ProblemKind::NoDraws
+            }]
+        );
+        assert!(parse("1,2\n\n", None).unwrap_err().contains(&Problem {
+            line: 3,
+            kind: ProblemKind::NoBoards
+        }));
+    }
+
+    #[test]
+    fn test_seven() {
+        let values: Vec<String> = (1..50).map(|val| val.to_string()).collect();
+        let mut grid = Grid::new(values.iter().map(|val| val.as_str()).collect());
+        assert_eq!((grid.width, grid.height), (7, 7));
+
+        for number in (7..50).step_by(7) {
+            grid.check_number(number);
+        }
+        assert!(grid.check_col(6));
+        assert!(grid.is_bingo());
+        assert_eq!(grid.unmarked_sum(), 1225 - 196);
+    }
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("7x3"), (7, 3));
+    }
 }
@@ -0,0 +1,60 @@
+/// Set of cells of a grid, one bit per cell.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Marks(Vec<u64>);
+
+impl Marks {
+    /// No cell set out of `len`.
+    pub fn new(len: usize) -> Marks {
+        Marks(vec![0; len.div_ceil(64)])
+    }
+
+    pub fn from_cells(cells: &[usize], len: usize) -> Marks {
+        let mut result = Marks::new(len);
+        for cell in cells {
+            result.set(*cell);
+        }
+        result
+    }
+
+    pub fn set(&mut self, cell: usize) {
+        self.0[cell / 64] |= 1 << (cell % 64);
+    }
+
+    pub fn get(&self, cell: usize) -> bool {
+        self.0[cell / 64] & (1 << (cell % 64)) != 0
+    }
+
+    /// Every cell of `other` is set.
+    pub fn contains(&self, other: &Marks) -> bool {
+        self.0
+            .iter()
+            .zip(&other.0)
+            .all(|(current, other)| current & other == *other)
+    }
+}
+
+#[cfg(test)]
+mod test_marks {
+    use super::*;
+
+    #[test]
+    fn test_set_get() {
+        let mut marks = Marks::new(130);
+        marks.set(0);
+        marks.set(129);
+
+        assert!(marks.get(0));
+        assert!(marks.get(129));
+        assert!(!marks.get(64));
+        assert_eq!(marks, Marks::from_cells(&[129, 0], 130));
+    }
+
+    #[test]
+    fn test_contains() {
+        let marks = Marks::from_cells(&[1, 2, 3, 70], 100);
+
+        assert!(marks.contains(&Marks::from_cells(&[1, 70], 100)));
+        assert!(marks.contains(&Marks::new(100)));
+        assert!(!marks.contains(&Marks::from_cells(&[1, 71], 100)));
+    }
+}
@@ -0,0 +1,285 @@
+use std::thread;
+
+use super::game::Game;
+use super::pattern::Pattern;
+use super::{patterns, read_input, Grid};
+
+/// 95% two sided.
+const Z: f64 = 1.96;
+
+/// SplitMix64, small and good enough to shuffle draws.
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        Rng(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut value = self.0;
+        value = (value ^ (value >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        value = (value ^ (value >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        value ^ (value >> 31)
+    }
+
+    /// Uniform in `0..bound`, rejecting the values that would bias the
+    /// modulo.
+    pub fn below(&mut self, bound: u64) -> u64 {
+        let zone = u64::MAX - u64::MAX % bound;
+        loop {
+            let value = self.next_u64();
+            if value < zone {
+                return value % bound;
+            }
+        }
+    }
+
+    /// Fisher-Yates.
+    pub fn shuffle<T>(&mut self, values: &mut [T]) {
+        for index in (1..values.len()).rev() {
+            let other = self.below(index as u64 + 1) as usize;
+            values.swap(index, other);
+        }
+    }
+}
+
+/// Outcome of a board over every trial.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Estimate {
+    pub board: usize,
+    /// Trials where the board won first, a tie counts for every board.
+    pub first: u64,
+    /// Trials where the board won at all.
+    pub wins: u64,
+    turn_sum: u64,
+    turn_squares: u64,
+}
+
+impl Estimate {
+    fn merge(&mut self, other: &Estimate) {
+        self.first += other.first;
+        self.wins += other.wins;
+        self.turn_sum += other.turn_sum;
+        self.turn_squares += other.turn_squares;
+    }
+
+    /// Share of `trials` won first with its Wilson score interval.
+    pub fn probability(&self, trials: u64) -> (f64, (f64, f64)) {
+        let n = trials as f64;
+        let p = self.first as f64 / n;
+        let center = (p + Z * Z / (2.0 * n)) / (1.0 + Z * Z / n);
+        let half = Z * (p * (1.0 - p) / n + Z * Z / (4.0 * n * n)).sqrt() / (1.0 + Z * Z / n);
+        (p, ((center - half).max(0.0), (center + half).min(1.0)))
+    }
+
+    /// Mean win turn of the trials the board won, with its normal interval.
+    pub fn expected_turn(&self) -> Option<(f64, (f64, f64))> {
+        if self.wins == 0 {
+            return None;
+        }
+        let n = self.wins as f64;
+        let mean = self.turn_sum as f64 / n;
+        let variance = if self.wins > 1 {
+            ((self.turn_squares as f64 - n * mean * mean) / (n - 1.0)).max(0.0)
+        } else {
+            0.0
+        };
+        let half = Z * (variance / n).sqrt();
+        Some((mean, (mean - half, mean + half)))
+    }
+}
+
+/// Play the trials `worker`, `worker + step`, ... each with the pool
+/// shuffled by its own seed, so the result doesn't depend on the threads.
+fn run_trials(
+    pool: &[usize],
+    grids: &[Grid],
+    patterns: &[Pattern],
+    trials: (u64, u64, u64),
+    seed: u64,
+) -> Vec<Estimate> {
+    let (worker, step, count) = trials;
+    let mut estimates: Vec<Estimate> = (0..grids.len())
+        .map(|board| Estimate {
+            board,
+            ..Estimate::default()
+        })
+        .collect();
+    let mut draws = pool.to_vec();
+    for trial in (worker..count).step_by(step as usize) {
+        draws.copy_from_slice(pool);
+        Rng::new(seed ^ Rng::new(trial).next_u64()).shuffle(&mut draws);
+
+        let wins = Game::new(draws.clone(), grids.to_vec(), patterns.to_vec()).play();
+        let first = wins.first().map(|win| win.turn);
+        for win in &wins {
+            let estimate = &mut estimates[win.board];
+            estimate.wins += 1;
+            estimate.turn_sum += win.turn as u64;
+            estimate.turn_squares += (win.turn * win.turn) as u64;
+            if Some(win.turn) == first {
+                estimate.first += 1;
+            }
+        }
+    }
+    estimates
+}
+
+/// Estimate over `trials` shuffles of `pool` how often each board wins first
+/// and on which turn, on `threads` threads. Panics without trials, there
+/// would be no probability to give.
+pub fn simulate(
+    pool: &[usize],
+    grids: &[Grid],
+    patterns: &[Pattern],
+    trials: u64,
+    seed: u64,
+    threads: usize,
+) -> Vec<Estimate> {
+    assert!(trials > 0, "at least one trial is needed");
+    let threads = threads.clamp(1, trials as usize) as u64;
+    let results: Vec<Vec<Estimate>> = thread::scope(|scope| {
+        let handles: Vec<_> = (0..threads)
+            .map(|worker| {
+                scope.spawn(move || {
+                    run_trials(pool, grids, patterns, (worker, threads, trials), seed)
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .collect()
+    });
+
+    let mut estimates = results[0].clone();
+    for result in &results[1..] {
+        for (estimate, other) in estimates.iter_mut().zip(result) {
+            estimate.merge(other);
+        }
+    }
+    estimates
+}
+
+pub fn table(estimates: &[Estimate], trials: u64) -> String {
+    let mut result = String::from("board  p(first)       95% ci   E(turn)           95% ci\n");
+    for estimate in estimates {
+        let (p, (low, high)) = estimate.probability(trials);
+        let turn = match estimate.expected_turn() {
+            Some((mean, (low, high))) => {
+                format!("{:>9.2} [{:>7.2}, {:>7.2}]", mean, low, high)
+            }
+            None => format!("{:>9}", "-"),
+        };
+        result.push_str(&format!(
+            "{:>5} {:>9.4} [{:.4}, {:.4}] {}\n",
+            estimate.board, p, low, high, turn
+        ));
+    }
+    result
+}
+
+/// `montecarlo [--trials=N] [--seed=S] [--threads=T] [--size=WxH]
+/// [--pattern=...] [file]`, the draws of the input are the pool.
+pub fn run(args: &[String]) {
+    let mut filename = "input";
+    let mut trials = 10_000;
+    let mut seed = 0;
+    let mut threads = thread::available_parallelism()
+        .map(|val| val.get())
+        .unwrap_or(1);
+    for arg in args {
+        match arg.split_once('=') {
+            Some(("--trials", val)) => trials = val.parse().unwrap(),
+            Some(("--seed", val)) => seed = val.parse().unwrap(),
+            Some(("--threads", val)) => threads = val.parse().unwrap(),
+            Some(_) => (),
+            None => filename = arg,
+        }
+    }
+
+    let input = read_input(filename, args);
+    let estimates = simulate(
+        &input.draws,
+        &input.grids,
+        &patterns(args),
+        trials,
+        seed,
+        threads,
+    );
+    print!("{}", table(&estimates, trials));
+}
+
+#[cfg(test)]
+mod test_montecarlo {
+    use super::super::pattern;
+    use super::*;
+
+    #[test]
+    fn test_rng() {
+        let mut first = Rng::new(42);
+        let mut second = Rng::new(42);
+        assert_eq!(first.next_u64(), second.next_u64());
+        assert!((0..1000).all(|_| first.below(7) < 7));
+
+        let mut values: Vec<usize> = (0..50).collect();
+        second.shuffle(&mut values);
+        assert_ne!(values, (0..50).collect::<Vec<usize>>());
+        values.sort_unstable();
+        assert_eq!(values, (0..50).collect::<Vec<usize>>());
+    }
+
+    #[test]
+    fn test_certain() {
+        let grids = vec![Grid::new(vec!["1"]), Grid::new(vec!["9"])];
+        let estimates = simulate(&[1], &grids, &pattern::STANDARD, 10, 1, 2);
+
+        assert_eq!(estimates[0].first, 10);
+        assert_eq!(estimates[0].probability(10).0, 1.0);
+        assert_eq!(estimates[0].expected_turn(), Some((1.0, (1.0, 1.0))));
+        assert_eq!(estimates[1].wins, 0);
+        assert_eq!(estimates[1].expected_turn(), None);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_no_trials() {
+        let grids = vec![Grid::new(vec!["1"])];
+        simulate(&[1], &grids, &pattern::STANDARD, 0, 1, 2);
+    }
+
+    #[test]
+    fn test_fair() {
+        let grids = vec![Grid::new(vec!["1"]), Grid::new(vec!["2"])];
+        let trials = 4000;
+        let estimates = simulate(&[1, 2], &grids, &pattern::STANDARD, trials, 7, 4);
+
+        assert_eq!(estimates[0].first + estimates[1].first, trials);
+        for estimate in &estimates {
+            let (p, (low, high)) = estimate.probability(trials);
+            assert!(low < 0.5 && 0.5 < high, "{} not around 0.5", p);
+            let (turn, _) = estimate.expected_turn().unwrap();
+            assert!((turn - 1.5).abs() < 0.05);
+        }
+    }
+
+    #[test]
+    fn test_threads() {
+        let grids = vec![
+            Grid::new(vec!["1", "2", "3", "4"]),
+            Grid::new(vec!["3", "5", "6", "1"]),
+        ];
+        let pool: Vec<usize> = (1..7).collect();
+        let single = simulate(&pool, &grids, &pattern::STANDARD, 500, 3, 1);
+
+        assert_eq!(
+            simulate(&pool, &grids, &pattern::STANDARD, 500, 3, 3),
+            single
+        );
+        assert_ne!(
+            simulate(&pool, &grids, &pattern::STANDARD, 500, 4, 1),
+            single
+        );
+    }
+}
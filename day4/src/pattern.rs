@@ -0,0 +1,312 @@
+use std::fmt;
+
+use super::marks::Marks;
+
+/// Shape drawn with `#` for the cells to mark and `.` for the others, one
+/// line per row:
+///
+/// ```text
+/// .#.
+/// ###
+/// .#.
+/// ```
+///
+/// A mask smaller than the grid wins anywhere it fits.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Mask {
+    name: String,
+    cells: Vec<bool>,
+    width: usize,
+    height: usize,
+}
+
+impl Mask {
+    /// The errors name the line, starting at 1.
+    pub fn parse(name: &str, text: &str) -> Result<Mask, String> {
+        // numbered before leaving out the blank lines
+        let lines: Vec<(usize, &str)> = text
+            .lines()
+            .map(|line| line.trim())
+            .enumerate()
+            .filter(|(_, line)| !line.is_empty())
+            .collect();
+        let width = lines.first().ok_or("empty mask")?.1.len();
+        let mut cells = Vec::new();
+        for (index, line) in &lines {
+            if line.len() != width {
+                return Err(format!(
+                    "line {}: {} cells instead of {}",
+                    index + 1,
+                    line.len(),
+                    width
+                ));
+            }
+            for current in line.chars() {
+                match current {
+                    '#' => cells.push(true),
+                    '.' => cells.push(false),
+                    _ => return Err(format!("line {}: unexpected '{}'", index + 1, current)),
+                }
+            }
+        }
+        if !cells.contains(&true) {
+            return Err(String::from("the mask has no cell to mark"));
+        }
+        Ok(Mask {
+            name: String::from(name),
+            cells,
+            width,
+            height: lines.len(),
+        })
+    }
+}
+
+impl Mask {
+    /// The rows joined by `/`, as read back by `Pattern::from_spec`.
+    fn rows(&self) -> String {
+        self.cells
+            .chunks(self.width)
+            .map(|row| {
+                row.iter()
+                    .map(|cell| if *cell { '#' } else { '.' })
+                    .collect::<String>()
+            })
+            .collect::<Vec<String>>()
+            .join("/")
+    }
+}
+
+/// Set of cells that make a board win once all marked.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Pattern {
+    Line,
+    Column,
+    /// Top left to bottom right, square grids only.
+    Diagonal,
+    /// Top right to bottom left, square grids only.
+    AntiDiagonal,
+    Corners,
+    /// Both diagonals, square grids only.
+    X,
+    Blackout,
+    Mask(Mask),
+}
+
+/// Rows and columns, the puzzle rules.
+pub const STANDARD: [Pattern; 2] = [Pattern::Line, Pattern::Column];
+
+impl Pattern {
+    /// Built in patterns by name, as printed by `Display`.
+    pub fn parse(name: &str) -> Option<Pattern> {
+        match name {
+            "line" => Some(Pattern::Line),
+            "column" => Some(Pattern::Column),
+            "diagonal" => Some(Pattern::Diagonal),
+            "anti-diagonal" => Some(Pattern::AntiDiagonal),
+            "corners" => Some(Pattern::Corners),
+            "x" => Some(Pattern::X),
+            "blackout" => Some(Pattern::Blackout),
+            _ => None,
+        }
+    }
+
+    /// Name of a built in pattern, or `mask <rows> <name>` with the rows of
+    /// the mask joined by `/`.
+    pub fn to_spec(&self) -> String {
+        match self {
+            Pattern::Mask(mask) => format!("mask {} {}", mask.rows(), mask.name),
+            _ => self.to_string(),
+        }
+    }
+
+    pub fn from_spec(spec: &str) -> Result<Pattern, String> {
+        match spec.strip_prefix("mask ") {
+            Some(rest) => {
+                let (rows, name) = rest.split_once(' ').ok_or("mask without a name")?;
+                Mask::parse(name, &rows.replace('/', "\n")).map(Pattern::Mask)
+            }
+            None => Pattern::parse(spec).ok_or(format!("unknown pattern {}", spec)),
+        }
+    }
+
+    /// Every way to win on a `width` x `height` grid, as lists of cells
+    /// indexed line by line.
+    pub fn cells(&self, width: usize, height: usize) -> Vec<Vec<usize>> {
+        let square = width == height;
+        let diagonal = || (0..width).map(|index| index * width + index).collect();
+        let anti_diagonal = || {
+            (0..width)
+                .map(|index| index * width + width - 1 - index)
+                .collect()
+        };
+        match self {
+            Pattern::Line => (0..height)
+                .map(|line| (line * width..(line + 1) * width).collect())
+                .collect(),
+            Pattern::Column => (0..width)
+                .map(|col| (0..height).map(|line| line * width + col).collect())
+                .collect(),
+            Pattern::Diagonal if square => vec![diagonal()],
+            Pattern::AntiDiagonal if square => vec![anti_diagonal()],
+            Pattern::X if square => {
+                let mut cells: Vec<usize> = diagonal();
+                cells.extend(anti_diagonal());
+                cells.sort_unstable();
+                cells.dedup();
+                vec![cells]
+            }
+            Pattern::Diagonal | Pattern::AntiDiagonal | Pattern::X => Vec::new(),
+            Pattern::Corners if width > 0 && height > 0 => {
+                let mut cells = vec![0, width - 1, (height - 1) * width, height * width - 1];
+                cells.sort_unstable();
+                cells.dedup();
+                vec![cells]
+            }
+            Pattern::Corners => Vec::new(),
+            Pattern::Blackout => vec![(0..width * height).collect()],
+            Pattern::Mask(mask) => {
+                let mut result = Vec::new();
+                for top in 0..(height + 1).saturating_sub(mask.height) {
+                    for left in 0..(width + 1).saturating_sub(mask.width) {
+                        result.push(
+                            (0..mask.cells.len())
+                                .filter(|index| mask.cells[*index])
+                                .map(|index| {
+                                    (top + index / mask.width) * width + left + index % mask.width
+                                })
+                                .collect(),
+                        );
+                    }
+                }
+                result
+            }
+        }
+    }
+}
+
+/// Patterns turned into cell masks for one grid size, so a win check is a few
+/// mask comparisons.
+pub struct Compiled {
+    masks: Vec<Vec<Marks>>,
+}
+
+impl Compiled {
+    pub fn new(patterns: &[Pattern], width: usize, height: usize) -> Compiled {
+        let len = width * height;
+        Compiled {
+            masks: patterns
+                .iter()
+                .map(|pattern| {
+                    pattern
+                        .cells(width, height)
+                        .iter()
+                        .map(|cells| Marks::from_cells(cells, len))
+                        .collect()
+                })
+                .collect(),
+        }
+    }
+
+    /// Index of the first pattern fully marked.
+    pub fn find(&self, marks: &Marks) -> Option<usize> {
+        self.masks
+            .iter()
+            .position(|masks| masks.iter().any(|mask| marks.contains(mask)))
+    }
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Pattern::Line => "line",
+            Pattern::Column => "column",
+            Pattern::Diagonal => "diagonal",
+            Pattern::AntiDiagonal => "anti-diagonal",
+            Pattern::Corners => "corners",
+            Pattern::X => "x",
+            Pattern::Blackout => "blackout",
+            Pattern::Mask(mask) => &mask.name,
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[cfg(test)]
+mod test_pattern {
+    use super::*;
+
+    #[test]
+    fn test_cells() {
+        assert_eq!(
+            Pattern::Line.cells(3, 2),
+            vec![vec![0, 1, 2], vec![3, 4, 5]]
+        );
+        assert_eq!(
+            Pattern::Column.cells(3, 2),
+            vec![vec![0, 3], vec![1, 4], vec![2, 5]]
+        );
+        assert_eq!(Pattern::Diagonal.cells(3, 3), vec![vec![0, 4, 8]]);
+        assert_eq!(Pattern::AntiDiagonal.cells(3, 3), vec![vec![2, 4, 6]]);
+        assert_eq!(Pattern::X.cells(3, 3), vec![vec![0, 2, 4, 6, 8]]);
+        assert_eq!(Pattern::Corners.cells(3, 2), vec![vec![0, 2, 3, 5]]);
+        assert_eq!(Pattern::Blackout.cells(2, 2), vec![vec![0, 1, 2, 3]]);
+
+        assert!(Pattern::Diagonal.cells(3, 2).is_empty());
+        assert_eq!(Pattern::Corners.cells(1, 1), vec![vec![0]]);
+    }
+
+    #[test]
+    fn test_mask() {
+        let mask = Mask::parse("tee", "###\n.#.\n").unwrap();
+        let pattern = Pattern::Mask(mask);
+
+        assert_eq!(pattern.to_string(), "tee");
+        assert_eq!(pattern.cells(3, 2), vec![vec![0, 1, 2, 4]]);
+        // fits at two heights and two offsets
+        assert_eq!(
+            pattern.cells(4, 3),
+            vec![
+                vec![0, 1, 2, 5],
+                vec![1, 2, 3, 6],
+                vec![4, 5, 6, 9],
+                vec![5, 6, 7, 10]
+            ]
+        );
+        assert!(pattern.cells(2, 2).is_empty());
+    }
+
+    #[test]
+    fn test_mask_errors() {
+        assert_eq!(
+            Mask::parse("bad", "##\n#\n"),
+            Err(String::from("line 2: 1 cells instead of 2"))
+        );
+        assert_eq!(
+            Mask::parse("bad", "#x\n"),
+            Err(String::from("line 1: unexpected 'x'"))
+        );
+        assert_eq!(
+            Mask::parse("bad", "\n##\n\n#.\n#\n"),
+            Err(String::from("line 5: 1 cells instead of 2"))
+        );
+        assert!(Mask::parse("bad", "..\n").is_err());
+        assert!(Mask::parse("bad", "").is_err());
+    }
+
+    #[test]
+    fn test_parse() {
+        for pattern in [Pattern::AntiDiagonal, Pattern::X, Pattern::Blackout] {
+            assert_eq!(Pattern::parse(&pattern.to_string()), Some(pattern));
+        }
+        assert_eq!(Pattern::parse("star"), None);
+    }
+
+    #[test]
+    fn test_spec() {
+        let plus = Pattern::Mask(Mask::parse("my plus", ".#.\n###\n.#.").unwrap());
+        assert_eq!(plus.to_spec(), "mask .#./###/.#. my plus");
+        assert_eq!(Pattern::from_spec(&plus.to_spec()), Ok(plus));
+        assert_eq!(Pattern::from_spec("corners"), Ok(Pattern::Corners));
+        assert!(Pattern::from_spec("mask ##").is_err());
+    }
+}
@@ -0,0 +1,151 @@
+use std::collections::HashMap;
+
+use super::game::Win;
+use super::pattern::Pattern;
+use super::Grid;
+
+/// Win turns computed from the draw order instead of playing: a set of cells
+/// completes on the last draw of its numbers and a board wins on the first
+/// set completed.
+pub struct Predictor {
+    draws: Vec<usize>,
+    /// First turn of each number, starting at 1.
+    turns: HashMap<usize, usize>,
+    patterns: Vec<Pattern>,
+    /// Cells of the patterns for each grid size met so far.
+    cells: HashMap<(usize, usize), Vec<Vec<Vec<usize>>>>,
+}
+
+impl Predictor {
+    pub fn new(draws: Vec<usize>, patterns: Vec<Pattern>) -> Predictor {
+        let mut turns = HashMap::new();
+        for (turn, number) in draws.iter().enumerate() {
+            turns.entry(*number).or_insert(turn + 1);
+        }
+        Predictor {
+            draws,
+            turns,
+            patterns,
+            cells: HashMap::new(),
+        }
+    }
+
+    /// The win of `grid`, `None` when it never wins.
+    pub fn predict(&mut self, board: usize, grid: &Grid) -> Option<Win> {
+        let patterns = &self.patterns;
+        let cells = self
+            .cells
+            .entry((grid.width, grid.height))
+            .or_insert_with(|| {
+                patterns
+                    .iter()
+                    .map(|pattern| pattern.cells(grid.width, grid.height))
+                    .collect()
+            });
+        let turns: Vec<Option<usize>> = grid
+            .numbers
+            .iter()
+            .map(|number| self.turns.get(number).copied())
+            .collect();
+
+        // first turn each pattern completes on
+        let completed: Vec<Option<usize>> = cells
+            .iter()
+            .map(|sets| {
+                sets.iter()
+                    .filter_map(|set| {
+                        set.iter()
+                            .try_fold(0, |last, cell| turns[*cell].map(|val| val.max(last)))
+                    })
+                    .min()
+            })
+            .collect();
+        let turn = completed.iter().flatten().min().copied()?;
+        let pattern = completed.iter().position(|val| *val == Some(turn)).unwrap();
+
+        let number = self.draws[turn - 1];
+        let unmarked_sum = grid
+            .numbers
+            .iter()
+            .zip(&turns)
+            .filter(|(_, current)| current.map(|val| val > turn).unwrap_or(true))
+            .map(|(value, _)| *value as u32)
+            .sum();
+        Some(Win {
+            board,
+            turn,
+            number,
+            unmarked_sum,
+            score: number as u32 * unmarked_sum,
+            pattern: self.patterns[pattern].clone(),
+        })
+    }
+
+    /// Every win, in the order `Game::play` returns them.
+    pub fn predict_all(&mut self, grids: &[Grid]) -> Vec<Win> {
+        let mut wins: Vec<Win> = grids
+            .iter()
+            .enumerate()
+            .filter_map(|(board, grid)| self.predict(board, grid))
+            .collect();
+        wins.sort_by_key(|win| (win.turn, win.board));
+        wins
+    }
+}
+
+/// The wins happening on `turn`.
+pub fn winners_at(wins: &[Win], turn: usize) -> Vec<&Win> {
+    wins.iter().filter(|win| win.turn == turn).collect()
+}
+
+#[cfg(test)]
+mod test_predict {
+    use super::super::game::test_game::EXAMPLE;
+    use super::super::game::Game;
+    use super::super::{parse, pattern, Input};
+    use super::*;
+
+    #[test]
+    fn test_predict() {
+        let Input { draws, grids, .. } = parse(EXAMPLE, None).unwrap();
+        let mut predictor = Predictor::new(draws, pattern::STANDARD.to_vec());
+        let wins = predictor.predict_all(&grids);
+
+        assert_eq!(wins.first().map(|win| win.score), Some(4512));
+        assert_eq!(wins.last().map(|win| win.score), Some(1924));
+        assert_eq!(
+            winners_at(&wins, 12)
+                .iter()
+                .map(|win| win.board)
+                .collect::<Vec<_>>(),
+            vec![2]
+        );
+        assert!(winners_at(&wins, 1).is_empty());
+    }
+
+    #[test]
+    fn test_same_as_game() {
+        let Input { draws, grids, .. } = parse(EXAMPLE, None).unwrap();
+        for patterns in [
+            pattern::STANDARD.to_vec(),
+            vec![Pattern::Diagonal, Pattern::Column],
+            vec![Pattern::Corners, Pattern::X, Pattern::Blackout],
+        ] {
+            let game = Game::new(draws.clone(), grids.clone(), patterns.clone());
+            let mut predictor = Predictor::new(draws.clone(), patterns);
+            assert_eq!(predictor.predict_all(&grids), game.play());
+        }
+    }
+
+    #[test]
+    fn test_never_wins() {
+        // 9 is never drawn, 2 is drawn twice
+        let Input { draws, grids, .. } = parse("1,2,2,3\n\n1 9\n9 9\n\n1 2\n3 9\n", None).unwrap();
+        let mut predictor = Predictor::new(draws, pattern::STANDARD.to_vec());
+
+        assert_eq!(predictor.predict(0, &grids[0]), None);
+        let win = predictor.predict(1, &grids[1]).unwrap();
+        assert_eq!((win.turn, win.number, win.unmarked_sum), (2, 2, 12));
+        assert_eq!(win.pattern, Pattern::Line);
+    }
+}
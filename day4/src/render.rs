@@ -0,0 +1,244 @@
+use std::fmt;
+use std::slice;
+
+use super::game::Game;
+use super::pattern::Pattern;
+use super::{patterns, read_input, Grid};
+
+const RESET: &str = "\x1b[0m";
+const MARKED: &str = "\x1b[1;32m";
+const WINNING: &str = "\x1b[1;30;43m";
+
+/// How marked cells stand out.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Style {
+    /// Colors, for a terminal.
+    Ansi,
+    /// `[12]` for a marked cell and `<12>` for a winning one.
+    Plain,
+}
+
+/// Lines of a board, every one `width` characters wide once the color codes
+/// are left out.
+pub struct Rendered {
+    pub lines: Vec<String>,
+    pub width: usize,
+}
+
+/// Draw `grid` with the numbers right aligned, the `winning` cells stand out
+/// from the other marked ones.
+pub fn render(grid: &Grid, style: Style, winning: &[usize]) -> Rendered {
+    let digits = grid
+        .numbers
+        .iter()
+        .map(|val| val.to_string().len())
+        .max()
+        .unwrap_or(1);
+    let lines = (0..grid.height)
+        .map(|line| {
+            (line * grid.width..(line + 1) * grid.width)
+                .map(|index| {
+                    let cell = grid.cell(index);
+                    let state = if winning.contains(&index) {
+                        Some(WINNING)
+                    } else if cell.is_marked() {
+                        Some(MARKED)
+                    } else {
+                        None
+                    };
+                    match (style, state) {
+                        (_, None) => format!(" {:>digits$} ", cell.value()),
+                        (Style::Ansi, Some(color)) => {
+                            format!("{} {:>digits$} {}", color, cell.value(), RESET)
+                        }
+                        (Style::Plain, Some(WINNING)) => format!("<{:>digits$}>", cell.value()),
+                        (Style::Plain, Some(_)) => format!("[{:>digits$}]", cell.value()),
+                    }
+                })
+                .collect()
+        })
+        .collect();
+    Rendered {
+        lines,
+        width: grid.width * (digits + 2),
+    }
+}
+
+impl fmt::Display for Grid {
+    /// Plain style, one line per row.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for line in render(self, Style::Plain, &[]).lines {
+            writeln!(f, "{}", line)?;
+        }
+        Ok(())
+    }
+}
+
+/// Boards next to each other, `columns` per row of boards.
+pub fn side_by_side(boards: &[Rendered], columns: usize) -> String {
+    let mut result = String::new();
+    for group in boards.chunks(columns.max(1)) {
+        let height = group
+            .iter()
+            .map(|board| board.lines.len())
+            .max()
+            .unwrap_or(0);
+        for line in 0..height {
+            let parts: Vec<String> = group
+                .iter()
+                .map(|board| match board.lines.get(line) {
+                    Some(current) => current.clone(),
+                    None => " ".repeat(board.width),
+                })
+                .collect();
+            result.push_str(parts.join("   ").trim_end());
+            result.push('\n');
+        }
+        result.push('\n');
+    }
+    result
+}
+
+/// Print every board after each draw until the last board won, the winning
+/// cells of the boards done are highlighted.
+pub fn replay(
+    draws: &[usize],
+    grids: Vec<Grid>,
+    patterns: &[Pattern],
+    style: Style,
+    columns: usize,
+) -> String {
+    let mut result = String::new();
+    let mut game = Game::new(draws.to_vec(), grids, patterns.to_vec());
+    let mut won: Vec<Vec<usize>> = vec![Vec::new(); game.grids.len()];
+    while !game.is_over() {
+        let wins = game.step().to_vec();
+        result.push_str(&format!(
+            "draw {}: {}\n",
+            game.turn,
+            game.draws[game.turn - 1]
+        ));
+        for win in wins {
+            result.push_str(&format!("board {} wins, score {}\n", win.board, win.score));
+            won[win.board] = game.grids[win.board]
+                .winning_cells(slice::from_ref(&win.pattern))
+                .unwrap_or_default();
+        }
+        let rendered: Vec<Rendered> = game
+            .grids
+            .iter()
+            .zip(&won)
+            .map(|(grid, cells)| render(grid, style, cells))
+            .collect();
+        result.push_str(&side_by_side(&rendered, columns));
+    }
+    result
+}
+
+/// `replay [--plain] [--columns=N] [--size=WxH] [--pattern=...] [file]`
+pub fn run(args: &[String]) {
+    let mut filename = "input";
+    let mut style = Style::Ansi;
+    let mut columns = 5;
+    for arg in args {
+        match arg.split_once('=') {
+            Some(("--columns", val)) => columns = val.parse().unwrap(),
+            Some(_) => (),
+            None if arg == "--plain" => style = Style::Plain,
+            None => filename = arg,
+        }
+    }
+
+    let input = read_input(filename, args);
+    print!(
+        "{}",
+        replay(&input.draws, input.grids, &patterns(args), style, columns)
+    );
+}
+
+#[cfg(test)]
+mod test_render {
+    use super::super::game::test_game::EXAMPLE;
+    use super::super::{parse, pattern, Input};
+    use super::*;
+
+    fn grid() -> Grid {
+        Grid::new(vec!["1", "2", "3", "4", "15", "6", "7", "8", "9"])
+    }
+
+    #[test]
+    fn test_display() {
+        let mut grid = grid();
+        grid.check_number(15);
+        assert_eq!(
+            grid.to_string(),
+            "  1   2   3 \n  4 [15]  6 \n  7   8   9 \n"
+        );
+    }
+
+    #[test]
+    fn test_winning() {
+        let mut grid = grid();
+        for number in [4, 15, 6, 9] {
+            grid.check_number(number);
+        }
+        let cells = grid.winning_cells(&pattern::STANDARD).unwrap();
+        assert_eq!(cells, vec![3, 4, 5]);
+
+        let rendered = render(&grid, Style::Plain, &cells);
+        assert_eq!(rendered.lines[1], "< 4><15>< 6>");
+        assert_eq!(rendered.lines[2], "  7   8 [ 9]");
+        assert_eq!(rendered.width, 12);
+
+        let rendered = render(&grid, Style::Ansi, &cells);
+        assert_eq!(
+            rendered.lines[2],
+            format!("  7   8 {}  9 {}", MARKED, RESET)
+        );
+    }
+
+    #[test]
+    fn test_side_by_side() {
+        let small = Grid::new(vec!["1"]);
+        let boards = vec![
+            render(&grid(), Style::Plain, &[]),
+            render(&small, Style::Plain, &[]),
+            render(&small, Style::Plain, &[]),
+        ];
+
+        assert_eq!(
+            side_by_side(&boards, 2),
+            "  1   2   3     1\n  4  15   6\n  7   8   9\n\n 1\n\n"
+        );
+    }
+
+    #[test]
+    fn test_replay() {
+        let grids = vec![grid(), Grid::new(vec!["3", "5", "9", "7"])];
+        let output = replay(&[3, 9, 6, 5], grids, &pattern::STANDARD, Style::Plain, 2);
+
+        assert!(output.starts_with("draw 1: 3\n"));
+        assert!(output.contains("draw 2: 9\nboard 1 wins, score 108\n"));
+        assert!(output.contains("draw 3: 6\nboard 0 wins, score 222\n"));
+        // both boards won after 3 draws
+        assert!(!output.contains("draw 4"));
+        assert!(output.ends_with("  1   2 < 3>   <3> 5\n  4  15 < 6>   <9> 7\n  7   8 < 9>\n\n"));
+    }
+
+    #[test]
+    fn test_replay_wins() {
+        let Input { draws, grids, .. } = parse(EXAMPLE, None).unwrap();
+        let output = replay(&draws, grids.clone(), &pattern::STANDARD, Style::Plain, 3);
+
+        let wins = Game::new(draws, grids, pattern::STANDARD.to_vec()).play();
+        let expected: Vec<String> = wins
+            .iter()
+            .map(|win| format!("board {} wins, score {}", win.board, win.score))
+            .collect();
+        let found: Vec<&str> = output
+            .lines()
+            .filter(|line| line.starts_with("board "))
+            .collect();
+        assert_eq!(found, expected);
+    }
+}
@@ -0,0 +1,245 @@
+//! Bingo over TCP, one line per message.
+//!
+//! A player sends `join <name>` and gets `welcome <id>` then its board as
+//! `card <width> <height> <numbers...>`. Once every player joined the server
+//! sends `start <players>`, then for each draw `draw <turn> <number>` and
+//! waits for every player to answer `pass <turn>` or `bingo! <turn>`. An
+//! answer to an earlier draw is ignored. A wrong claim gets `rejected`, a
+//! right one is announced to all as `winner <name> <score>` and the game ends
+//! with `end` after that draw. A connection that doesn't send its join within
+//! `JOIN_TIMEOUT` is dropped, a player that doesn't answer a draw within
+//! `ANSWER_TIMEOUT` passes.
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::{read_input, Grid};
+
+/// How long a connection can wait before sending `join`, so a silent one
+/// doesn't keep the others out.
+const JOIN_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// How long the players have to answer a draw, so a silent one doesn't stall
+/// the game.
+const ANSWER_TIMEOUT: Duration = Duration::from_secs(3);
+
+enum Event {
+    Line(usize, String),
+    Left(usize),
+}
+
+struct Player {
+    name: String,
+    grid: Grid,
+    stream: TcpStream,
+    active: bool,
+}
+
+impl Player {
+    fn send(&mut self, message: &str) {
+        if writeln!(self.stream, "{}", message).is_err() {
+            self.active = false;
+        }
+    }
+}
+
+/// A winner and its score.
+#[derive(Debug, PartialEq)]
+pub struct Winner {
+    pub name: String,
+    pub score: u32,
+}
+
+/// Forward the lines of `reader` to `events` until the player leaves.
+fn listen(id: usize, reader: BufReader<TcpStream>, events: Sender<Event>) {
+    for line in reader.lines() {
+        match line {
+            Ok(line) => {
+                if events
+                    .send(Event::Line(id, line.trim().to_string()))
+                    .is_err()
+                {
+                    return;
+                }
+            }
+            Err(_) => break,
+        }
+    }
+    let _ = events.send(Event::Left(id));
+}
+
+/// Wait for `count` players on `listener`, dealing the grids in order. There
+/// must be a grid for each player.
+fn join(listener: &TcpListener, grids: Vec<Grid>, count: usize) -> (Vec<Player>, Receiver<Event>) {
+    let (sender, events) = mpsc::channel();
+    let mut players: Vec<Player> = Vec::new();
+    let mut grids = grids.into_iter();
+    while players.len() < count {
+        let (mut stream, _) = match listener.accept() {
+            Ok(connection) => connection,
+            Err(_) => continue,
+        };
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let mut line = String::new();
+        if stream.set_read_timeout(Some(JOIN_TIMEOUT)).is_err() {
+            continue;
+        }
+        if reader.read_line(&mut line).is_err() {
+            let _ = writeln!(stream, "error join timed out");
+            continue;
+        }
+        if stream.set_read_timeout(None).is_err() {
+            continue;
+        }
+        let name = match line.trim().strip_prefix("join ") {
+            Some(name) if !name.trim().is_empty() => name.trim().to_string(),
+            _ => {
+                let _ = writeln!(stream, "error expected join <name>");
+                continue;
+            }
+        };
+        let grid = grids.next().expect("a grid for each player");
+
+        let id = players.len();
+        let numbers: Vec<String> = grid.numbers.iter().map(|val| val.to_string()).collect();
+        let mut player = Player {
+            name,
+            stream,
+            active: true,
+            grid,
+        };
+        player.send(&format!("welcome {}", id));
+        player.send(&format!(
+            "card {} {} {}",
+            player.grid.width,
+            player.grid.height,
+            numbers.join(" ")
+        ));
+        players.push(player);
+
+        let sender = sender.clone();
+        thread::spawn(move || listen(id, reader, sender));
+    }
+    (players, events)
+}
+
+fn broadcast(players: &mut [Player], message: &str) {
+    for player in players.iter_mut().filter(|player| player.active) {
+        player.send(message);
+    }
+}
+
+/// Play `draws` with `players` until a draw has winners or the draws run out.
+fn play(mut players: Vec<Player>, events: Receiver<Event>, draws: &[usize]) -> Vec<Winner> {
+    let start = format!("start {}", players.len());
+    broadcast(&mut players, &start);
+
+    let mut winners = Vec::new();
+    for (turn, number) in draws.iter().enumerate() {
+        for player in players.iter_mut() {
+            player.grid.check_number(*number);
+        }
+        broadcast(&mut players, &format!("draw {} {}", turn + 1, number));
+
+        let deadline = Instant::now() + ANSWER_TIMEOUT;
+        let mut waiting: Vec<bool> = players.iter().map(|player| player.active).collect();
+        while waiting.contains(&true) {
+            let timeout = deadline.saturating_duration_since(Instant::now());
+            let (id, line) = match events.recv_timeout(timeout) {
+                Ok(Event::Line(id, line)) => (id, line),
+                Ok(Event::Left(id)) => {
+                    players[id].active = false;
+                    waiting[id] = false;
+                    continue;
+                }
+                // the players still waiting pass
+                Err(RecvTimeoutError::Timeout) | Err(RecvTimeoutError::Disconnected) => break,
+            };
+            let player = &mut players[id];
+            let (answer, answered) = match line
+                .split_once(' ')
+                .map(|(answer, val)| (answer, val.parse::<usize>()))
+            {
+                Some((answer, Ok(answered))) => (answer, answered),
+                _ => {
+                    player.send("error expected pass <turn> or bingo! <turn>");
+                    continue;
+                }
+            };
+            match answer {
+                // late answer to a draw that is over
+                _ if answered < turn + 1 => (),
+                _ if answered > turn + 1 => player.send("error no such draw yet"),
+                _ if !waiting[id] => player.send("error wait for the next draw"),
+                "pass" => waiting[id] = false,
+                "bingo!" if player.grid.is_bingo() => {
+                    winners.push(Winner {
+                        name: player.name.clone(),
+                        score: *number as u32 * player.grid.unmarked_sum(),
+                    });
+                    waiting[id] = false;
+                }
+                "bingo!" => {
+                    player.send("rejected");
+                    waiting[id] = false;
+                }
+                _ => player.send("error expected pass <turn> or bingo! <turn>"),
+            }
+        }
+
+        for winner in &winners {
+            broadcast(
+                &mut players,
+                &format!("winner {} {}", winner.name, winner.score),
+            );
+        }
+        if !winners.is_empty() || !players.iter().any(|player| player.active) {
+            break;
+        }
+    }
+    broadcast(&mut players, "end");
+    winners
+}
+
+/// Deal `grids` to `count` players joining on `listener` and play a game.
+/// Panics with fewer grids than players.
+pub fn serve(
+    listener: &TcpListener,
+    draws: &[usize],
+    grids: Vec<Grid>,
+    count: usize,
+) -> Vec<Winner> {
+    let (players, events) = join(listener, grids, count);
+    play(players, events, draws)
+}
+
+/// `serve [--port=P] [--players=N] [--size=WxH] [file]`, the port 0 picks a
+/// free one.
+pub fn run(args: &[String]) {
+    let mut filename = "input";
+    let mut port = 7878;
+    let mut count = 2;
+    for arg in args {
+        match arg.split_once('=') {
+            Some(("--port", val)) => port = val.parse().unwrap(),
+            Some(("--players", val)) => count = val.parse().unwrap(),
+            Some(_) => (),
+            None => filename = arg,
+        }
+    }
+
+    let input = read_input(filename, args);
+    if count > input.grids.len() {
+        panic!("{} players for {} boards", count, input.grids.len());
+    }
+    let listener = TcpListener::bind(("127.0.0.1", port)).expect("can't listen");
+    println!("listening on {}", listener.local_addr().unwrap());
+    std::io::stdout().flush().unwrap();
+
+    for winner in serve(&listener, &input.draws, input.grids, count) {
+        println!("winner {} {}", winner.name, winner.score);
+    }
+}
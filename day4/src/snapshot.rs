@@ -0,0 +1,359 @@
+//! Text snapshot of a game, one item per line so two snapshots diff well:
+//!
+//! ```text
+//! draws 7,4,9,5,11
+//! turn 3
+//! pattern line
+//! pattern column
+//! win board=0 turn=3 number=9 unmarked=10 pattern=line
+//! board 0 2x2
+//! [7] [4]
+//!   1   2
+//! ```
+//!
+//! A marked cell is between brackets, blank lines and lines starting with
+//! `#` are skipped. The items can come in any order, the rows of a board
+//! right after it.
+
+use std::fs;
+
+use super::game::{Game, Win};
+use super::pattern::Pattern;
+use super::{patterns, read_input, Grid};
+
+impl Game {
+    pub fn save(&self) -> String {
+        let draws: Vec<String> = self.draws.iter().map(|val| val.to_string()).collect();
+        let mut result = format!("draws {}\nturn {}\n", draws.join(","), self.turn);
+        for pattern in &self.patterns {
+            result.push_str(&format!("pattern {}\n", pattern.to_spec()));
+        }
+        for win in &self.wins {
+            result.push_str(&format!(
+                "win board={} turn={} number={} unmarked={} pattern={}\n",
+                win.board, win.turn, win.number, win.unmarked_sum, win.pattern
+            ));
+        }
+        for (board, grid) in self.grids.iter().enumerate() {
+            result.push_str(&format!("board {} {}x{}\n", board, grid.width, grid.height));
+            let size = grid
+                .numbers
+                .iter()
+                .map(|val| val.to_string().len() + 2)
+                .max()
+                .unwrap_or(0);
+            for line in 0..grid.height {
+                let cells: Vec<String> = (line * grid.width..(line + 1) * grid.width)
+                    .map(|index| {
+                        let cell = grid.cell(index);
+                        let token = if cell.is_marked() {
+                            format!("[{}]", cell.value())
+                        } else {
+                            cell.value().to_string()
+                        };
+                        format!("{:>size$}", token)
+                    })
+                    .collect();
+                result.push_str(cells.join(" ").trim_end());
+                result.push('\n');
+            }
+        }
+        result
+    }
+
+    /// The errors name the line, starting at 1.
+    pub fn load(text: &str) -> Result<Game, String> {
+        let mut draws = None;
+        let mut turn = 0;
+        let mut patterns = Vec::new();
+        // resolved once every pattern is read
+        let mut raw_wins = Vec::new();
+        let mut grids = Vec::new();
+
+        let mut lines = text
+            .lines()
+            .enumerate()
+            .map(|(index, line)| (index + 1, line.trim()))
+            .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'));
+        while let Some((line, raw)) = lines.next() {
+            let at = |message: String| format!("line {}: {}", line, message);
+            let (key, rest) = raw.split_once(' ').unwrap_or((raw, ""));
+            match key {
+                "draws" => {
+                    draws = Some(
+                        rest.split(',')
+                            .map(|val| number(val).map_err(at))
+                            .collect::<Result<Vec<usize>, String>>()?,
+                    )
+                }
+                "turn" => turn = number(rest).map_err(at)?,
+                "pattern" => patterns.push(Pattern::from_spec(rest).map_err(at)?),
+                "win" => raw_wins.push((line, rest)),
+                "board" => {
+                    let (index, size) = rest
+                        .split_once(' ')
+                        .ok_or_else(|| at(String::from("expected board <index> <W>x<H>")))?;
+                    if number(index).map_err(at)? != grids.len() {
+                        return Err(at(format!("expected board {}", grids.len())));
+                    }
+                    let (width, height) = size
+                        .split_once('x')
+                        .ok_or_else(|| at(format!("bad size {}", size)))?;
+                    let (width, height) = (number(width).map_err(at)?, number(height).map_err(at)?);
+
+                    let mut values = Vec::new();
+                    let mut marked = Vec::new();
+                    for _ in 0..height {
+                        let (line, raw) = lines
+                            .next()
+                            .ok_or_else(|| at(String::from("missing rows")))?;
+                        let tokens: Vec<&str> = raw.split_whitespace().collect();
+                        if tokens.len() != width {
+                            return Err(format!(
+                                "line {}: {} cells instead of {}",
+                                line,
+                                tokens.len(),
+                                width
+                            ));
+                        }
+                        for token in tokens {
+                            match token
+                                .strip_prefix('[')
+                                .and_then(|val| val.strip_suffix(']'))
+                            {
+                                Some(value) => {
+                                    marked.push(values.len());
+                                    values.push(value);
+                                }
+                                None => values.push(token),
+                            }
+                        }
+                    }
+                    if let Some(value) = values.iter().find(|val| number(val).is_err()) {
+                        return Err(at(format!("'{}' is not a number", value)));
+                    }
+                    let mut grid = Grid::with_size(values, width, height);
+                    for index in marked {
+                        grid.mark(index);
+                    }
+                    grids.push(grid);
+                }
+                _ => return Err(at(format!("unknown item {}", key))),
+            }
+        }
+
+        let wins = raw_wins
+            .into_iter()
+            .map(|(line, raw)| {
+                parse_win(raw, &patterns).map_err(|err| format!("line {}: {}", line, err))
+            })
+            .collect::<Result<Vec<Win>, String>>()?;
+        let draws = draws.ok_or("no draws")?;
+        if turn > draws.len() {
+            return Err(format!("turn {} after the {} draws", turn, draws.len()));
+        }
+        if let Some(win) = wins.iter().find(|win| win.board >= grids.len()) {
+            return Err(format!("win of the missing board {}", win.board));
+        }
+        Ok(Game::resume(draws, grids, patterns, turn, wins))
+    }
+}
+
+fn number(raw: &str) -> Result<usize, String> {
+    raw.trim()
+        .parse()
+        .map_err(|_| format!("'{}' is not a number", raw))
+}
+
+/// `board=B turn=T number=N unmarked=U pattern=P`, the pattern last as a mask
+/// name can hold spaces.
+fn parse_win(raw: &str, patterns: &[Pattern]) -> Result<Win, String> {
+    let (fields, name) = raw.split_once(" pattern=").ok_or("win without pattern")?;
+    let pattern = patterns
+        .iter()
+        .find(|pattern| pattern.to_string() == name)
+        .ok_or(format!("unknown pattern {}", name))?;
+    let mut values = [None; 4];
+    for field in fields.split_whitespace() {
+        let (key, value) = field
+            .split_once('=')
+            .ok_or(format!("bad field {}", field))?;
+        let slot = ["board", "turn", "number", "unmarked"]
+            .iter()
+            .position(|val| *val == key)
+            .ok_or(format!("unknown field {}", key))?;
+        values[slot] = Some(number(value)?);
+    }
+    match values {
+        [Some(board), Some(turn), Some(number), Some(unmarked)] => Ok(Win {
+            board,
+            turn,
+            number,
+            unmarked_sum: unmarked as u32,
+            score: (number * unmarked) as u32,
+            pattern: pattern.clone(),
+        }),
+        _ => Err(String::from("win needs board, turn, number and unmarked")),
+    }
+}
+
+/// Draw `turns` numbers, or up to the end, printing the wins.
+fn advance(game: &mut Game, turns: Option<usize>) {
+    let mut done = 0;
+    while !game.is_over() && turns.map(|val| done < val).unwrap_or(true) {
+        for win in game.step() {
+            println!(
+                "turn {}: board {} wins with {}, score {}",
+                win.turn, win.board, win.pattern, win.score
+            );
+        }
+        done += 1;
+    }
+    if game.is_over() {
+        if let Some(first) = game.wins.first() {
+            println!("part1 {} ({})", first.score, first.pattern);
+        }
+        if let Some(last) = game.wins.last() {
+            println!("part2 {} ({})", last.score, last.pattern);
+        }
+    } else {
+        println!("paused after {} of {} draws", game.turn, game.draws.len());
+    }
+}
+
+/// `--turns=N` and the positional arguments.
+fn split_args(args: &[String]) -> (Option<usize>, Vec<&str>) {
+    let mut turns = None;
+    let mut positional = Vec::new();
+    for arg in args {
+        match arg.split_once('=') {
+            Some(("--turns", val)) => turns = Some(val.parse().unwrap()),
+            Some(_) => (),
+            None => positional.push(arg.as_str()),
+        }
+    }
+    (turns, positional)
+}
+
+/// `save <snapshot> [--turns=N] [--size=WxH] [--pattern=...] [file]` starts a
+/// game and saves it after `N` draws.
+pub fn run_save(args: &[String]) {
+    let (turns, positional) = split_args(args);
+    let snapshot = positional.first().expect("save <snapshot> [file]");
+    let input = read_input(positional.get(1).unwrap_or(&"input"), args);
+
+    let mut game = Game::new(input.draws, input.grids, patterns(args));
+    advance(&mut game, turns);
+    fs::write(snapshot, game.save()).expect("can't write snapshot");
+}
+
+/// `resume <snapshot> [--turns=N]` continues a saved game, the snapshot is
+/// updated in place.
+pub fn run_resume(args: &[String]) {
+    let (turns, positional) = split_args(args);
+    let snapshot = positional.first().expect("resume <snapshot>");
+    let text = fs::read_to_string(snapshot).expect("can't read snapshot");
+
+    let mut game = Game::load(&text).unwrap_or_else(|err| panic!("{}: {}", snapshot, err));
+    advance(&mut game, turns);
+    fs::write(snapshot, game.save()).expect("can't write snapshot");
+}
+
+#[cfg(test)]
+mod test_snapshot {
+    use super::super::game::test_game::EXAMPLE;
+    use super::super::{parse, pattern, Input};
+    use super::*;
+
+    fn game() -> Game {
+        let Input { draws, grids, .. } = parse(EXAMPLE, None).unwrap();
+        Game::new(draws, grids, pattern::STANDARD.to_vec())
+    }
+
+    #[test]
+    fn test_save() {
+        let mut game = Game::new(
+            vec![7, 4, 9, 5, 11],
+            vec![Grid::new(vec!["7", "4", "1", "12"])],
+            pattern::STANDARD.to_vec(),
+        );
+        game.step();
+        game.step();
+
+        assert_eq!(
+            game.save(),
+            "draws 7,4,9,5,11\n\
+             turn 2\n\
+             pattern line\n\
+             pattern column\n\
+             win board=0 turn=2 number=4 unmarked=13 pattern=line\n\
+             board 0 2x2\n \
+             [7]  [4]\n   \
+             1   12\n"
+        );
+    }
+
+    #[test]
+    fn test_resume() {
+        let expected = game().play();
+        for turns in [0, 5, 12, 13, 27] {
+            let mut game = game();
+            for _ in 0..turns {
+                game.step();
+            }
+            let text = game.save();
+            let resumed = Game::load(&text).unwrap();
+            assert_eq!(resumed.save(), text);
+            assert_eq!(resumed.play(), expected);
+        }
+    }
+
+    #[test]
+    fn test_mask() {
+        let Input { draws, grids, .. } = parse(EXAMPLE, None).unwrap();
+        let plus = Pattern::from_spec("mask .#./###/.#. small plus").unwrap();
+        let mut game = Game::new(draws, grids, vec![plus]);
+        while game.wins.is_empty() {
+            game.step();
+        }
+
+        let resumed = Game::load(&game.save()).unwrap();
+        assert_eq!(resumed.wins, game.wins);
+        assert_eq!(resumed.wins[0].pattern.to_string(), "small plus");
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            Game::load("draws 1,2\nturn x\n").err(),
+            Some(String::from("line 2: 'x' is not a number"))
+        );
+        assert_eq!(
+            Game::load("draws 1,2\n\nboard 0 2x1\n1 2 3\n").err(),
+            Some(String::from("line 4: 3 cells instead of 2"))
+        );
+        assert_eq!(
+            Game::load("draws 1\nturn 2\n").err(),
+            Some(String::from("turn 2 after the 1 draws"))
+        );
+        assert_eq!(
+            Game::load("draws 1\nwin board=0 turn=1 number=1 unmarked=0 pattern=x\n").err(),
+            Some(String::from("line 2: unknown pattern x"))
+        );
+        assert!(Game::load("turn 1\n").is_err());
+    }
+
+    #[test]
+    fn test_win_before_pattern() {
+        let text = "draws 7,4\n\
+                    win board=0 turn=2 number=4 unmarked=13 pattern=line\n\
+                    turn 2\n\
+                    pattern line\n\
+                    board 0 2x2\n\
+                    [7] [4]\n\
+                    1 12\n";
+        let game = Game::load(text).unwrap();
+        assert_eq!(game.wins[0].pattern, Pattern::Line);
+        assert!(game.is_over());
+    }
+}
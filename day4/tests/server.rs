@@ -0,0 +1,251 @@
+use std::env;
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpStream;
+use std::process::{Child, ChildStdout, Command, Stdio};
+use std::time::Duration;
+
+const INPUT: &str = "1,2,3,4,5,6
+
+1 2
+7 8
+
+9 3
+10 4
+";
+
+struct Server {
+    child: Child,
+    stdout: BufReader<ChildStdout>,
+    address: String,
+}
+
+impl Server {
+    fn start(name: &str, players: usize) -> Server {
+        let filename = env::temp_dir().join(format!("day4-{}-{}", name, std::process::id()));
+        fs::write(&filename, INPUT).unwrap();
+        let mut child = Command::new(env!("CARGO_BIN_EXE_day4"))
+            .arg("serve")
+            .arg("--port=0")
+            .arg(format!("--players={}", players))
+            .arg(&filename)
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let mut stdout = BufReader::new(child.stdout.take().unwrap());
+        let mut line = String::new();
+        stdout.read_line(&mut line).unwrap();
+        // the input is read before listening
+        fs::remove_file(&filename).unwrap();
+        let address = line
+            .trim()
+            .strip_prefix("listening on ")
+            .unwrap()
+            .to_string();
+        Server {
+            child,
+            stdout,
+            address,
+        }
+    }
+
+    /// What the server printed once the game is over.
+    fn finish(mut self) -> String {
+        let mut output = String::new();
+        for line in (&mut self.stdout).lines() {
+            output.push_str(&line.unwrap());
+            output.push('\n');
+        }
+        assert!(self.child.wait().unwrap().success());
+        output
+    }
+}
+
+struct Client {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+}
+
+impl Client {
+    fn connect(server: &Server) -> Client {
+        let writer = TcpStream::connect(&server.address).unwrap();
+        writer
+            .set_read_timeout(Some(Duration::from_secs(10)))
+            .unwrap();
+        Client {
+            reader: BufReader::new(writer.try_clone().unwrap()),
+            writer,
+        }
+    }
+
+    fn send(&mut self, message: &str) {
+        writeln!(self.writer, "{}", message).unwrap();
+    }
+
+    fn recv(&mut self) -> String {
+        let mut line = String::new();
+        self.reader.read_line(&mut line).unwrap();
+        line.trim().to_string()
+    }
+
+    fn join(server: &Server, name: &str) -> Client {
+        let mut client = Client::connect(server);
+        client.send(&format!("join {}", name));
+        client
+    }
+}
+
+#[test]
+fn test_claims() {
+    let server = Server::start("claims", 2);
+    let mut alice = Client::join(&server, "alice");
+    assert_eq!(alice.recv(), "welcome 0");
+    assert_eq!(alice.recv(), "card 2 2 1 2 7 8");
+    let mut bob = Client::join(&server, "bob");
+    assert_eq!(bob.recv(), "welcome 1");
+    assert_eq!(bob.recv(), "card 2 2 9 3 10 4");
+
+    for client in [&mut alice, &mut bob] {
+        assert_eq!(client.recv(), "start 2");
+        assert_eq!(client.recv(), "draw 1 1");
+    }
+    alice.send("pass 1");
+    bob.send("bingo! 1");
+    assert_eq!(bob.recv(), "rejected");
+
+    for client in [&mut alice, &mut bob] {
+        assert_eq!(client.recv(), "draw 2 2");
+    }
+    alice.send("bingo! 2");
+    bob.send("pass 2");
+    for client in [&mut alice, &mut bob] {
+        assert_eq!(client.recv(), "winner alice 30");
+        assert_eq!(client.recv(), "end");
+    }
+
+    assert_eq!(server.finish(), "winner alice 30\n");
+}
+
+#[test]
+fn test_late_claim() {
+    let server = Server::start("late", 2);
+    let mut alice = Client::join(&server, "alice");
+    let mut bob = Client::join(&server, "bob");
+    for client in [&mut alice, &mut bob] {
+        client.recv();
+        client.recv();
+        assert_eq!(client.recv(), "start 2");
+    }
+
+    // alice never claims her line, bob's column is full on the 4th draw
+    for turn in 1..5 {
+        for client in [&mut alice, &mut bob] {
+            assert!(client.recv().starts_with(&format!("draw {} ", turn)));
+        }
+        alice.send(&format!("pass {}", turn));
+        let answer = if turn == 4 { "bingo!" } else { "pass" };
+        bob.send(&format!("{} {}", answer, turn));
+    }
+    assert_eq!(alice.recv(), "winner bob 76");
+    assert_eq!(alice.recv(), "end");
+
+    assert_eq!(server.finish(), "winner bob 76\n");
+}
+
+#[test]
+fn test_bad_join() {
+    let server = Server::start("join", 1);
+    let mut stranger = Client::connect(&server);
+    stranger.send("hello");
+    assert_eq!(stranger.recv(), "error expected join <name>");
+
+    let mut alice = Client::join(&server, "alice");
+    assert_eq!(alice.recv(), "welcome 0");
+    alice.recv();
+    assert_eq!(alice.recv(), "start 1");
+    drop(alice);
+
+    assert_eq!(server.finish(), "");
+}
+
+#[test]
+fn test_stale_answer() {
+    let server = Server::start("stale", 2);
+    let mut alice = Client::join(&server, "alice");
+    let mut bob = Client::join(&server, "bob");
+    for client in [&mut alice, &mut bob] {
+        client.recv();
+        client.recv();
+        assert_eq!(client.recv(), "start 2");
+        assert_eq!(client.recv(), "draw 1 1");
+    }
+    alice.send("pass 1");
+    bob.send("pass 1");
+
+    for client in [&mut alice, &mut bob] {
+        assert_eq!(client.recv(), "draw 2 2");
+    }
+    // a repeated answer to the first draw doesn't count for the second
+    alice.send("pass 1");
+    alice.send("bingo! 2");
+    bob.send("pass 2");
+    assert_eq!(alice.recv(), "winner alice 30");
+
+    assert_eq!(server.finish(), "winner alice 30\n");
+}
+
+#[test]
+fn test_silent_join() {
+    let server = Server::start("silent", 1);
+    let mut silent = Client::connect(&server);
+
+    let mut alice = Client::join(&server, "alice");
+    assert_eq!(silent.recv(), "error join timed out");
+    assert_eq!(alice.recv(), "welcome 0");
+    alice.recv();
+    assert_eq!(alice.recv(), "start 1");
+    drop(alice);
+
+    assert_eq!(server.finish(), "");
+}
+
+#[test]
+fn test_too_many_players() {
+    let filename = env::temp_dir().join(format!("day4-crowd-{}", std::process::id()));
+    fs::write(&filename, INPUT).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_day4"))
+        .arg("serve")
+        .arg("--port=0")
+        .arg("--players=3")
+        .arg(&filename)
+        .output()
+        .unwrap();
+    fs::remove_file(&filename).unwrap();
+
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("3 players for 2 boards"));
+}
+
+#[test]
+fn test_silent_player() {
+    let server = Server::start("quiet", 2);
+    let mut alice = Client::join(&server, "alice");
+    let mut bob = Client::join(&server, "bob");
+    for client in [&mut alice, &mut bob] {
+        client.recv();
+        client.recv();
+        assert_eq!(client.recv(), "start 2");
+    }
+
+    // bob never answers, each draw goes on without him
+    assert_eq!(alice.recv(), "draw 1 1");
+    alice.send("pass 1");
+    assert_eq!(alice.recv(), "draw 2 2");
+    alice.send("bingo! 2");
+    assert_eq!(alice.recv(), "winner alice 30");
+    assert_eq!(alice.recv(), "end");
+    drop(bob);
+
+    assert_eq!(server.finish(), "winner alice 30\n");
+}